@@ -0,0 +1,135 @@
+use std::{
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+/// Unbounded non-negative integer stored as decimal digits, for answers that outgrow even a `u128`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigDecimal {
+    /// Least significant digit first, without leading zeros (so zero has no digits)
+    digits: Vec<u8>,
+}
+
+impl BigDecimal {
+    /// Number made of `digits` in `radix`, most significant first.
+    pub fn from_digits(digits: impl IntoIterator<Item = u32>, radix: u32) -> Self {
+        let mut number = BigDecimal::default();
+
+        for digit in digits {
+            assert!(digit < radix, "Not a digit in radix {}: {}", radix, digit);
+            number.mul_add(radix, digit);
+        }
+
+        number
+    }
+
+    /// `self * factor + addend`, in place.
+    fn mul_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = u64::from(addend);
+
+        for digit in &mut self.digits {
+            let value = u64::from(*digit) * u64::from(factor) + carry;
+            *digit = (value % 10) as u8;
+            carry = value / 10;
+        }
+
+        while carry > 0 {
+            self.digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+}
+
+impl From<u128> for BigDecimal {
+    fn from(mut n: u128) -> Self {
+        let mut digits = Vec::new();
+
+        while n > 0 {
+            digits.push((n % 10) as u8);
+            n /= 10;
+        }
+
+        BigDecimal { digits }
+    }
+}
+
+impl AddAssign<&BigDecimal> for BigDecimal {
+    fn add_assign(&mut self, rhs: &BigDecimal) {
+        if self.digits.len() < rhs.digits.len() {
+            self.digits.resize(rhs.digits.len(), 0);
+        }
+
+        let mut carry = 0;
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            let sum = *digit + rhs.digits.get(i).copied().unwrap_or(0) + carry;
+            *digit = sum % 10;
+            carry = sum / 10;
+        }
+
+        if carry > 0 {
+            self.digits.push(carry);
+        }
+    }
+}
+
+impl Add<&BigDecimal> for BigDecimal {
+    type Output = BigDecimal;
+
+    fn add(mut self, rhs: &BigDecimal) -> BigDecimal {
+        self += rhs;
+        self
+    }
+}
+
+impl Sum for BigDecimal {
+    fn sum<I: Iterator<Item = BigDecimal>>(iter: I) -> Self {
+        iter.fold(BigDecimal::default(), |acc, n| acc + &n)
+    }
+}
+
+impl Display for BigDecimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        let digits: String = self
+            .digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from(b'0' + digit))
+            .collect();
+        write!(f, "{}", digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_matches_u128() {
+        let values = [0, 7, 95, 999_999, u64::MAX as u128, 10u128.pow(30) - 1];
+        let expected: u128 = values.iter().sum();
+
+        let sum: BigDecimal = values.into_iter().map(BigDecimal::from).sum();
+        assert_eq!(sum, BigDecimal::from(expected));
+        assert_eq!(sum.to_string(), expected.to_string());
+
+        assert_eq!(BigDecimal::from_digits([0, 0, 4, 2], 10).to_string(), "42");
+        assert_eq!(BigDecimal::from_digits([0], 10).to_string(), "0");
+
+        let huge = BigDecimal::from_digits([9; 50], 10) + &BigDecimal::from(1);
+        assert_eq!(huge.to_string(), format!("1{}", "0".repeat(50)));
+    }
+
+    #[test]
+    fn test_from_digits_radix() {
+        assert_eq!(BigDecimal::from_digits([15, 15], 16).to_string(), "255");
+        assert_eq!(
+            BigDecimal::from_digits([1; 3], 1 << 31).to_string(),
+            ((1u128 << 62) + (1 << 31) + 1).to_string()
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, env, fmt::Display, str::FromStr};
+
+/// Flags that take the next argument as their value. Any other flag is a switch and only takes a
+/// value written as `--flag=value`, so it can't swallow the day.
+const VALUED_FLAGS: &[&str] = &[
+    "backend",
+    "batteries",
+    "boundary",
+    "catalogue",
+    "count",
+    "crossings",
+    "dial-size",
+    "input",
+    "lock",
+    "neighbourhood",
+    "overflow",
+    "plan",
+    "predicate",
+    "radix",
+    "start",
+    "strategy",
+    "target",
+    "threshold",
+    "trace",
+];
+
+/// Command line arguments: an optional day and `--switch`, `--flag value` or `--flag=value`.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    day: Option<String>,
+    flags: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1))
+    }
+
+    pub fn parse(raw_args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = Args::default();
+        let mut raw_args = raw_args.into_iter().peekable();
+
+        while let Some(arg) = raw_args.next() {
+            match arg.strip_prefix("--") {
+                Some(flag) => {
+                    let (name, value) = match flag.split_once('=') {
+                        Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                        None if VALUED_FLAGS.contains(&flag) => {
+                            let value = raw_args.next_if(|next| !next.starts_with("--"));
+                            (flag.to_string(), value)
+                        }
+                        None => (flag.to_string(), None),
+                    };
+                    args.flags.insert(name, value);
+                }
+                None if args.day.is_none() => args.day = Some(arg),
+                None => panic!("Unexpected argument: {}", arg),
+            }
+        }
+
+        args
+    }
+
+    pub fn day(&self) -> Option<&str> {
+        self.day.as_deref()
+    }
+
+    /// Whether `--name` was given, with or without a value.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name)?.as_deref()
+    }
+
+    /// Parsed value of `--name`, panicking if it's there but not valid.
+    pub fn get<T>(&self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.value(name).map(|raw| {
+            raw.parse()
+                .unwrap_or_else(|e| panic!("Invalid value for --{}: {}", name, e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Args {
+        Args::parse(raw.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_switches_leave_the_day_alone() {
+        let args = parse("--stream 3 --radix 16 --highlight=4");
+        assert_eq!(args.day(), Some("3"));
+        assert!(args.flag("stream"));
+        assert_eq!(args.value("stream"), None);
+        assert_eq!(args.get("radix"), Some(16));
+        assert_eq!(args.get("highlight"), Some(4));
+
+        let args = parse("4 --boundary torus --waves");
+        assert_eq!(args.day(), Some("4"));
+        assert_eq!(args.value("boundary"), Some("torus"));
+        assert!(args.flag("waves"));
+    }
+}
@@ -1,4 +1,18 @@
-use crate::utils::{print_day_header, print_result, print_section, read_input};
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    str::FromStr,
+};
+
+use crate::cli::Args;
+use crate::day01_lock::{self, Lock};
+use crate::day01_plan;
+use crate::parsing::{Block, ParseError};
+use crate::utils::{
+    input_path, print_checked_result, print_day_header, print_result, print_section,
+    read_input_from,
+};
 
 const EXAMPLE: &str = "L68
 L30
@@ -15,112 +29,551 @@ const DIAL_SIZE: i32 = 100;
 const STARTING_POSITION: i32 = 50;
 const TARGET_POSITION: i32 = 0;
 
-#[derive(Debug, Copy, Clone)]
-enum Direction {
+/// Geometry of the safe dial. Defaults to the puzzle's, but can be changed with `--dial-size`,
+/// `--start` and `--target`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Dial {
+    pub(crate) size: i32,
+    pub(crate) start: i32,
+    pub(crate) target: i32,
+}
+
+impl Default for Dial {
+    fn default() -> Self {
+        Dial {
+            size: DIAL_SIZE,
+            start: STARTING_POSITION,
+            target: TARGET_POSITION,
+        }
+    }
+}
+
+impl Dial {
+    pub(crate) fn new(size: i32, start: i32, target: i32) -> Result<Self, String> {
+        if size <= 0 {
+            return Err(format!("Dial size must be positive, got {}", size));
+        }
+
+        for (name, position) in [("Start", start), ("Target", target)] {
+            if !(0..size).contains(&position) {
+                return Err(format!(
+                    "{} position {} is not on a dial of size {}",
+                    name, position, size
+                ));
+            }
+        }
+
+        Ok(Dial {
+            size,
+            start,
+            target,
+        })
+    }
+
+    fn from_args(args: &Args) -> Result<Self, String> {
+        let default = Dial::default();
+
+        Dial::new(
+            args.get("dial-size").unwrap_or(default.size),
+            args.get("start").unwrap_or(default.start),
+            args.get("target").unwrap_or(default.target),
+        )
+    }
+
+    pub(crate) fn target_name(&self) -> String {
+        match self.target {
+            0 => "zero".to_string(),
+            target => target.to_string(),
+        }
+    }
+}
+
+/// `size:start:target`, e.g. `100:50:0` for the puzzle's dial.
+impl FromStr for Dial {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers: Vec<i32> = s
+            .split(':')
+            .map(|n| n.trim().parse().map_err(|e| format!("Parse error: {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match numbers.as_slice() {
+            &[size, start, target] => Dial::new(size, start, target),
+            _ => Err(format!("Invalid dial, expected size:start:target: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Direction {
     Left,
     Right,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Rotation {
-    direction: Direction,
-    times: i32,
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Rotation {
+    pub(crate) direction: Direction,
+    pub(crate) times: i32,
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        };
+        write!(f, "{}{}", direction, self.times)
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw_direction = s.get(..1).ok_or("Missing direction")?;
+        let direction = match raw_direction {
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            _ => return Err(format!("Invalid direction: {}", raw_direction)),
+        };
+        let times = s[1..]
+            .parse()
+            .map_err(|e| format!("Invalid number of clicks: {}", e))?;
+
+        Ok(Rotation { direction, times })
+    }
 }
 
 fn parse(raw_data: &str) -> Vec<Rotation> {
-    raw_data
-        .lines()
-        .map(|r| {
-            let (raw_direction, raw_times) = r.split_at(1);
-            let times = raw_times.parse().expect("Failed to parse number");
-            let direction = match raw_direction {
-                "L" => Direction::Left,
-                "R" => Direction::Right,
-                _ => panic!("Invalid direction: {}", raw_direction),
-            };
-
-            Rotation { direction, times }
-        })
-        .collect()
+    Block::new(raw_data)
+        .parse_lines()
+        .unwrap_or_else(|e| panic!("Failed to parse rotations: {}", e))
 }
 
-fn apply_rotation(position: i32, rotation: &Rotation) -> i32 {
+/// Rotations along with the line of the input they are on.
+fn parse_numbered(raw_data: &str) -> Vec<(usize, Rotation)> {
+    let block = Block::new(raw_data);
+
+    block.lines().map(|line| line.line()).zip(parse(raw_data)).collect()
+}
+
+pub(crate) fn apply_rotation(dial: &Dial, position: i32, rotation: &Rotation) -> i32 {
     let delta = match rotation.direction {
         Direction::Left => -rotation.times,
         Direction::Right => rotation.times,
     };
 
-    (position + delta).rem_euclid(DIAL_SIZE)
+    (position + delta).rem_euclid(dial.size)
 }
 
-fn count_zero_crossings(start_position: i32, rotation: &Rotation) -> i32 {
-    let steps_to_first_zero = match (start_position, rotation.direction) {
-        (0, _) => DIAL_SIZE,
-        (pos, Direction::Right) => DIAL_SIZE - pos,
-        (pos, Direction::Left) => pos,
+/// How many times the dial passes over (or lands on) the target while rotating.
+pub(crate) fn count_target_crossings(dial: &Dial, start_position: i32, rotation: &Rotation) -> i32 {
+    let distance = match rotation.direction {
+        Direction::Right => dial.target - start_position,
+        Direction::Left => start_position - dial.target,
+    };
+
+    // Starting on the target, we need a full turn to get back to it
+    let steps_to_first_target = match distance.rem_euclid(dial.size) {
+        0 => dial.size,
+        steps => steps,
     };
 
-    if rotation.times < steps_to_first_zero {
+    if rotation.times < steps_to_first_target {
         0
     } else {
-        1 + (rotation.times - steps_to_first_zero) / DIAL_SIZE
+        1 + (rotation.times - steps_to_first_target) / dial.size
     }
 }
 
-fn part01(data: &[Rotation]) -> i32 {
-    let mut position = STARTING_POSITION;
-    let mut zero_count = 0;
+fn part01(dial: &Dial, data: &[Rotation]) -> i32 {
+    let mut position = dial.start;
+    let mut target_count = 0;
 
     for rotation in data {
-        position = apply_rotation(position, rotation);
+        position = apply_rotation(dial, position, rotation);
 
-        if position == TARGET_POSITION {
-            zero_count += 1;
+        if position == dial.target {
+            target_count += 1;
         }
     }
 
-    zero_count
+    target_count
 }
 
-fn part02(data: &[Rotation]) -> i32 {
-    let mut position = STARTING_POSITION;
-    let mut zero_count = 0;
+fn part02(dial: &Dial, data: &[Rotation]) -> i32 {
+    let mut position = dial.start;
+    let mut target_count = 0;
 
     for rotation in data {
-        zero_count += count_zero_crossings(position, rotation);
-        position = apply_rotation(position, rotation);
+        target_count += count_target_crossings(dial, position, rotation);
+        position = apply_rotation(dial, position, rotation);
+    }
+
+    target_count
+}
+
+/// Both answers at once, for inputs too big to keep in memory.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+struct Tally {
+    position: i32,
+    rotations: u64,
+    on_target: u64,
+    crossings: u64,
+}
+
+impl Tally {
+    fn new(dial: &Dial) -> Self {
+        Tally {
+            position: dial.start,
+            ..Tally::default()
+        }
+    }
+
+    fn apply(&mut self, dial: &Dial, rotation: &Rotation) {
+        self.crossings += count_target_crossings(dial, self.position, rotation) as u64;
+        self.position = apply_rotation(dial, self.position, rotation);
+        self.on_target += u64::from(self.position == dial.target);
+        self.rotations += 1;
+    }
+}
+
+#[derive(Debug)]
+enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "Failed to read input: {}", e),
+            StreamError::Parse(e) => write!(f, "Failed to parse rotations: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Reads rotations one line at a time, so memory use doesn't depend on the input size. Like `parse`,
+/// blank lines are only allowed before the first rotation and after the last one.
+fn solve_stream(dial: &Dial, mut reader: impl BufRead) -> Result<Tally, StreamError> {
+    let mut tally = Tally::new(dial);
+    let mut line = String::new();
+    let mut line_number = 0;
+    // First blank line since the last rotation, an error if another rotation follows it
+    let mut blank_line: Option<(usize, String)> = None;
+
+    let parse_at = |line_number: usize, raw_rotation: &str| {
+        raw_rotation.parse::<Rotation>().map_err(|e| {
+            StreamError::Parse(ParseError {
+                line: line_number,
+                column: None,
+                message: format!("Invalid value {:?}: {}", raw_rotation, e),
+            })
+        })
+    };
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(StreamError::Io)? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let raw_rotation = line.trim();
+        if raw_rotation.is_empty() {
+            if tally.rotations > 0 && blank_line.is_none() {
+                let text = line.trim_end_matches(['\n', '\r']).to_string();
+                blank_line = Some((line_number, text));
+            }
+            continue;
+        }
+
+        if let Some((blank_number, text)) = blank_line.take() {
+            parse_at(blank_number, &text)?;
+        }
+
+        tally.apply(dial, &parse_at(line_number, raw_rotation)?);
     }
 
-    zero_count
+    Ok(tally)
 }
 
-pub fn day01() {
+/// `--stream` solves the `--input` file (`-` for stdin) without loading it into memory.
+fn day01_stream(args: &Args, dial: &Dial) {
+    let tally = match args.value("input") {
+        Some("-") => solve_stream(dial, io::stdin().lock()),
+        path => {
+            let path = path.map_or_else(|| input_path(1), str::to_string);
+            let file =
+                File::open(&path).unwrap_or_else(|_| panic!("Failed to read input file: {}", path));
+            solve_stream(dial, BufReader::new(file))
+        }
+    };
+
     print_day_header(1);
 
+    print_section("Streamed (Actual)");
+    match tally {
+        Ok(tally) => {
+            print_result("Rotations read", tally.rotations);
+            print_result(
+                &format!("The dial points to {}", dial.target_name()),
+                format!("{} times", tally.on_target),
+            );
+            print_result(
+                &format!("The dial passed through {}", dial.target_name()),
+                format!("{} times", tally.crossings),
+            );
+        }
+        Err(e) => print_checked_result("The input couldn't be solved", Err::<String, _>(e)),
+    }
+}
+
+/// What happened on a single rotation, used to compare both parts line by line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct TraceStep {
+    line: usize,
+    rotation: Rotation,
+    start: i32,
+    end: i32,
+    on_target: bool,
+    crossings: i32,
+}
+
+fn trace(dial: &Dial, data: &[(usize, Rotation)]) -> Vec<TraceStep> {
+    let mut position = dial.start;
+
+    data.iter()
+        .map(|&(line, rotation)| {
+            let start = position;
+            position = apply_rotation(dial, position, &rotation);
+
+            TraceStep {
+                line,
+                rotation,
+                start,
+                end: position,
+                on_target: position == dial.target,
+                crossings: count_target_crossings(dial, start, &rotation),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TraceFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(TraceFormat::Table),
+            "csv" => Ok(TraceFormat::Csv),
+            "json" => Ok(TraceFormat::Json),
+            _ => Err(format!(
+                "Invalid trace format: {} (expected table, csv or json)",
+                s
+            )),
+        }
+    }
+}
+
+fn render_trace(steps: &[TraceStep], format: TraceFormat) -> String {
+    let mut out = String::new();
+
+    match format {
+        TraceFormat::Table => {
+            out.push_str("  line  rotation  start  end  on target  crossings\n");
+            for step in steps {
+                out.push_str(&format!(
+                    "{:>6}  {:>8}  {:>5}  {:>3}  {:>9}  {:>9}\n",
+                    step.line,
+                    step.rotation.to_string(),
+                    step.start,
+                    step.end,
+                    if step.on_target { "yes" } else { "no" },
+                    step.crossings
+                ));
+            }
+        }
+        TraceFormat::Csv => {
+            out.push_str("line,rotation,start,end,on_target,crossings\n");
+            for step in steps {
+                out.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    step.line, step.rotation, step.start, step.end, step.on_target, step.crossings
+                ));
+            }
+        }
+        TraceFormat::Json => {
+            let rows: Vec<String> = steps
+                .iter()
+                .map(|step| {
+                    format!(
+                        r#"  {{"line": {}, "rotation": "{}", "start": {}, "end": {}, "on_target": {}, "crossings": {}}}"#,
+                        step.line,
+                        step.rotation,
+                        step.start,
+                        step.end,
+                        step.on_target,
+                        step.crossings
+                    )
+                })
+                .collect();
+            out.push_str(&format!("[\n{}\n]\n", rows.join(",\n")));
+        }
+    }
+
+    out
+}
+
+pub fn day01(args: &Args) {
+    let dial = Dial::from_args(args).unwrap_or_else(|e| panic!("Invalid dial: {}", e));
+
+    // `--trace <format>` only prints the trace of the actual input, so it can be redirected
+    if let Some(format) = args.get("trace") {
+        print!(
+            "{}",
+            render_trace(&trace(&dial, &parse_numbered(&read_input_from(args, 1))), format)
+        );
+        return;
+    }
+
+    if let Some(positions) = args.value("plan") {
+        day01_plan::day01_plan(args, &dial, positions);
+        return;
+    }
+
+    if args.flag("stream") {
+        day01_stream(args, &dial);
+        return;
+    }
+
+    if let Some(lock) = args.get::<Lock>("lock") {
+        day01_lock::day01_lock(args, &lock);
+        return;
+    }
+
+    print_day_header(1);
+
+    let points_to = format!("The dial points to {}", dial.target_name());
+    let passed_through = format!("The dial passed through {}", dial.target_name());
+
     let example_data = parse(EXAMPLE);
-    let actual_data = parse(&read_input(1));
+    let actual_data = parse(&read_input_from(args, 1));
 
     print_section("Part 1 (Example)");
     print_result(
-        "The dial points to zero",
-        format!("{} times", part01(&example_data)),
+        &points_to,
+        format!("{} times", part01(&dial, &example_data)),
     );
 
     print_section("Part 1 (Actual)");
-    print_result(
-        "The dial points to zero",
-        format!("{} times", part01(&actual_data)),
-    );
+    print_result(&points_to, format!("{} times", part01(&dial, &actual_data)));
 
     print_section("Part 2 (Example)");
     print_result(
-        "The dial passed through zero",
-        format!("{} times", part02(&example_data)),
+        &passed_through,
+        format!("{} times", part02(&dial, &example_data)),
     );
 
     print_section("Part 2 (Actual)");
     print_result(
-        "The dial passed through zero",
-        format!("{} times", part02(&actual_data)),
+        &passed_through,
+        format!("{} times", part02(&dial, &actual_data)),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part02_example() {
+        assert_eq!(part02(&Dial::default(), &parse(EXAMPLE)), 6)
+    }
+
+    #[test]
+    fn test_stream_matches_both_parts() {
+        let dial = Dial::default();
+        let tally = solve_stream(&dial, format!("{}\n\n", EXAMPLE).as_bytes()).unwrap();
+
+        assert_eq!(
+            (tally.rotations, tally.on_target, tally.crossings),
+            (10, 3, 6)
+        );
+
+        let error = solve_stream(&dial, "L1\nR2\nX5\n".as_bytes()).unwrap_err();
+        assert!(matches!(
+            error,
+            StreamError::Parse(ParseError { line: 3, .. })
+        ));
+
+        // Blank lines are only allowed around the rotations, as when parsing the whole input
+        let raw = format!("\n\n{}\n\n", EXAMPLE);
+        assert_eq!(parse(&raw).len(), 10);
+        assert_eq!(solve_stream(&dial, raw.as_bytes()).unwrap().rotations, 10);
+
+        let gap = "L1\n\nR5\n";
+        assert!(Block::new(gap).parse_lines::<Rotation>().is_err());
+        assert!(matches!(
+            solve_stream(&dial, gap.as_bytes()).unwrap_err(),
+            StreamError::Parse(ParseError { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_trace_agrees_with_both_parts() {
+        let dial = Dial::default();
+        let data = parse(EXAMPLE);
+        let steps = trace(&dial, &parse_numbered(EXAMPLE));
+
+        assert_eq!(
+            steps.iter().filter(|s| s.on_target).count() as i32,
+            part01(&dial, &data)
+        );
+        assert_eq!(
+            steps.iter().map(|s| s.crossings).sum::<i32>(),
+            part02(&dial, &data)
+        );
+        assert_eq!((steps[0].start, steps[0].end), (50, 82));
+        assert!(render_trace(&steps, TraceFormat::Csv).starts_with("line,rotation,start,end"));
+        assert!(render_trace(&steps[..1], TraceFormat::Json).contains(r#""rotation": "L68""#));
+
+        // Lines are those of the input, blank lines before the rotations included
+        let steps = trace(&dial, &parse_numbered(&format!("\n\n{}", EXAMPLE)));
+        assert_eq!((steps[0].line, steps[9].line), (3, 12));
+    }
+
+    #[test]
+    fn test_target_crossings_match_click_by_click() {
+        for (size, target) in [(100, 0), (7, 3), (1, 0)] {
+            let dial = Dial::new(size, 0, target).unwrap();
+
+            for start in 0..size {
+                for times in 0..3 * size {
+                    for direction in [Direction::Left, Direction::Right] {
+                        let rotation = Rotation { direction, times };
+                        let step = match direction {
+                            Direction::Left => -1,
+                            Direction::Right => 1,
+                        };
+                        let expected = (1..=times)
+                            .filter(|i| (start + step * i).rem_euclid(size) == target)
+                            .count() as i32;
+
+                        assert_eq!(count_target_crossings(&dial, start, &rotation), expected);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+/// Several day 1 dials stacked into a combination lock. Unprefixed rotations turn every dial at
+/// once, while `2L15` only turns the second one.
+use std::str::FromStr;
+
+use crate::cli::Args;
+use crate::day01::{Dial, Rotation, apply_rotation, count_target_crossings};
+use crate::parsing::Block;
+use crate::utils::{
+    print_checked_result, print_day_header, print_result, print_section, read_input_from,
+};
+
+const EXAMPLE: &str = "L68
+2R3
+R18
+2R2
+L5
+1R5
+2L5
+R100";
+
+/// Only the first few alignments are listed, the rest are just counted
+const MAX_ALIGNMENTS_SHOWN: usize = 10;
+
+/// Dials given as `size:start:target` separated by commas, e.g. `100:50:0,10:5:0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lock {
+    dials: Vec<Dial>,
+}
+
+impl FromStr for Lock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dials: Vec<Dial> = Block::new(s).parse_list(',').map_err(|e| e.to_string())?;
+
+        if dials.is_empty() {
+            return Err("A lock needs at least one dial".to_string());
+        }
+
+        Ok(Lock { dials })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Instruction {
+    /// 0-based dial to turn, or `None` to turn all of them
+    dial: Option<usize>,
+    rotation: Rotation,
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let prefix_len = s.len() - s.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+        let (raw_dial, raw_rotation) = s.split_at(prefix_len);
+
+        let dial = match raw_dial {
+            "" => None,
+            raw_dial => match raw_dial.parse::<usize>() {
+                Ok(0) | Err(_) => return Err(format!("Invalid dial number: {}", raw_dial)),
+                Ok(n) => Some(n - 1),
+            },
+        };
+
+        Ok(Instruction {
+            dial,
+            rotation: raw_rotation.parse()?,
+        })
+    }
+}
+
+fn parse(raw_data: &str) -> Vec<Instruction> {
+    Block::new(raw_data)
+        .parse_lines()
+        .unwrap_or_else(|e| panic!("Failed to parse instructions: {}", e))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DialStats {
+    position: i32,
+    /// Rotations that left the dial on its target
+    target_hits: usize,
+    /// Clicks that passed over (or landed on) the target
+    crossings: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LockReport {
+    dials: Vec<DialStats>,
+    /// 1-based instructions after which every dial was on its target
+    alignments: Vec<usize>,
+}
+
+fn simulate(lock: &Lock, instructions: &[Instruction]) -> Result<LockReport, String> {
+    let mut dials: Vec<DialStats> = lock
+        .dials
+        .iter()
+        .map(|dial| DialStats {
+            position: dial.start,
+            target_hits: 0,
+            crossings: 0,
+        })
+        .collect();
+    let mut alignments = Vec::new();
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        let turned = match instruction.dial {
+            Some(idx) if idx >= dials.len() => {
+                return Err(format!(
+                    "Instruction {} turns dial {}, but the lock only has {}",
+                    i + 1,
+                    idx + 1,
+                    dials.len()
+                ));
+            }
+            Some(idx) => idx..idx + 1,
+            None => 0..dials.len(),
+        };
+
+        for idx in turned {
+            let (dial, stats) = (&lock.dials[idx], &mut dials[idx]);
+
+            stats.crossings += count_target_crossings(dial, stats.position, &instruction.rotation);
+            stats.position = apply_rotation(dial, stats.position, &instruction.rotation);
+
+            if stats.position == dial.target {
+                stats.target_hits += 1;
+            }
+        }
+
+        let aligned = dials
+            .iter()
+            .zip(&lock.dials)
+            .all(|(stats, dial)| stats.position == dial.target);
+
+        if aligned {
+            alignments.push(i + 1);
+        }
+    }
+
+    Ok(LockReport { dials, alignments })
+}
+
+fn print_report(lock: &Lock, report: Result<LockReport, String>) {
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            return print_checked_result("The lock couldn't be simulated", Err::<String, _>(e));
+        }
+    };
+
+    for (i, (dial, stats)) in lock.dials.iter().zip(&report.dials).enumerate() {
+        print_result(
+            &format!(
+                "Dial {} ({}:{}:{}) stopped on / passed through {}",
+                i + 1,
+                dial.size,
+                dial.start,
+                dial.target,
+                dial.target_name()
+            ),
+            format!("{} / {} times", stats.target_hits, stats.crossings),
+        );
+    }
+
+    let shown: Vec<String> = report
+        .alignments
+        .iter()
+        .take(MAX_ALIGNMENTS_SHOWN)
+        .map(|line| line.to_string())
+        .collect();
+    let more = match report.alignments.len().saturating_sub(MAX_ALIGNMENTS_SHOWN) {
+        0 => String::new(),
+        n => format!(" and {} more", n),
+    };
+
+    print_result(
+        "All dials were aligned",
+        match report.alignments.len() {
+            0 => "never".to_string(),
+            n => format!(
+                "{} times, after instructions {}{}",
+                n,
+                shown.join(", "),
+                more
+            ),
+        },
+    );
+}
+
+pub fn day01_lock(args: &Args, lock: &Lock) {
+    print_day_header(1);
+
+    let example_data = parse(EXAMPLE);
+    let actual_data = parse(&read_input_from(args, 1));
+
+    print_section("Lock (Example)");
+    print_report(lock, simulate(lock, &example_data));
+
+    print_section("Lock (Actual)");
+    print_report(lock, simulate(lock, &actual_data));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_dial_matches_both_parts() {
+        let lock: Lock = "100:50:0".parse().unwrap();
+        let day01_example = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82";
+        let report = simulate(&lock, &parse(day01_example)).unwrap();
+
+        assert_eq!(report.dials[0].target_hits, 3);
+        assert_eq!(report.dials[0].crossings, 6);
+        assert_eq!(report.alignments, vec![3, 6, 8]);
+    }
+
+    #[test]
+    fn test_prefixed_instructions() {
+        let lock: Lock = "100:50:0,10:5:0".parse().unwrap();
+        let report = simulate(&lock, &parse(EXAMPLE)).unwrap();
+
+        assert_eq!(
+            (report.dials[0].target_hits, report.dials[0].crossings),
+            (3, 4)
+        );
+        assert_eq!(
+            (report.dials[1].target_hits, report.dials[1].crossings),
+            (4, 21)
+        );
+        assert_eq!(report.alignments, vec![4, 7, 8]);
+
+        let error = simulate(&lock, &parse("3L1")).unwrap_err();
+        assert!(error.contains("only has 2"));
+        assert!("0L1".parse::<Instruction>().is_err());
+    }
+}
@@ -0,0 +1,148 @@
+/// The inverse of day 1: find the rotations with the fewest clicks that make the dial stop on a
+/// sequence of positions, printed in the same `L68` format the puzzle input uses.
+use crate::cli::Args;
+use crate::day01::{Dial, Direction, Rotation};
+use crate::graph;
+use crate::parsing::Block;
+
+/// Search state: how many positions were visited so far, where the dial is, how many times it
+/// passed its target and which way the last click went (`None` right after a visit).
+type Node = (usize, i32, usize, Option<Direction>);
+
+/// Rotations visiting `positions` in order with the fewest clicks. When `crossings` is given, the
+/// dial must also pass over (or land on) its target exactly that many times.
+fn plan(dial: &Dial, positions: &[i32], crossings: Option<usize>) -> Option<Vec<Rotation>> {
+    let max_crossings = crossings.unwrap_or(0);
+
+    let moves = graph::weighted_from_fn(|&(visited, position, passed, _): &Node| {
+        let mut edges = Vec::with_capacity(3);
+
+        // Stopping on the next position is free, but ends the current rotation
+        if positions.get(visited) == Some(&position) {
+            edges.push(((visited + 1, position, passed, None), 0));
+        }
+
+        if visited < positions.len() {
+            for (direction, step) in [(Direction::Left, -1), (Direction::Right, 1)] {
+                let next = (position + step).rem_euclid(dial.size);
+                let passed = passed + usize::from(crossings.is_some() && next == dial.target);
+
+                if passed <= max_crossings {
+                    edges.push(((visited, next, passed, Some(direction)), 1));
+                }
+            }
+        }
+
+        edges
+    });
+
+    let (_, path) = graph::astar(
+        &moves,
+        (0, dial.start, 0, None),
+        |&(visited, _, passed, _)| visited == positions.len() && passed == max_crossings,
+        |_| 0,
+    )?;
+
+    let mut rotations: Vec<Rotation> = Vec::new();
+    let mut ongoing = false;
+
+    for &(_, _, _, moved) in &path[1..] {
+        match (moved, rotations.last_mut()) {
+            (None, _) => ongoing = false,
+            (Some(direction), Some(last)) if ongoing && last.direction == direction => {
+                last.times += 1;
+            }
+            (Some(direction), _) => {
+                rotations.push(Rotation {
+                    direction,
+                    times: 1,
+                });
+                ongoing = true;
+            }
+        }
+    }
+
+    Some(rotations)
+}
+
+/// `--plan 0,37,12` prints the rotations for the dial set up with `--dial-size` and `--start`,
+/// optionally passing its `--target` exactly `--crossings` times.
+pub fn day01_plan(args: &Args, dial: &Dial, raw_positions: &str) {
+    let positions: Vec<i32> = Block::new(raw_positions)
+        .parse_list(',')
+        .unwrap_or_else(|e| panic!("Invalid positions: {}", e));
+
+    if let Some(position) = positions.iter().find(|p| !(0..dial.size).contains(p)) {
+        panic!(
+            "Position {} is not on a dial of size {}",
+            position, dial.size
+        );
+    }
+
+    match plan(dial, &positions, args.get("crossings")) {
+        Some(rotations) => {
+            for rotation in rotations {
+                println!("{}", rotation);
+            }
+        }
+        None => panic!("No sequence of rotations satisfies the constraints"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day01::{apply_rotation, count_target_crossings};
+
+    /// Replays the rotations, returning how many positions were visited, the crossings and clicks.
+    fn replay(dial: &Dial, positions: &[i32], rotations: &[Rotation]) -> (usize, i32, i32) {
+        let mut position = dial.start;
+        let mut visited = 0;
+        let mut crossings = 0;
+
+        let visit = |position: i32, visited: &mut usize| {
+            while positions.get(*visited) == Some(&position) {
+                *visited += 1;
+            }
+        };
+
+        visit(position, &mut visited);
+        for rotation in rotations {
+            crossings += count_target_crossings(dial, position, rotation);
+            position = apply_rotation(dial, position, rotation);
+            visit(position, &mut visited);
+        }
+
+        let clicks = rotations.iter().map(|r| r.times).sum();
+        (visited, crossings, clicks)
+    }
+
+    #[test]
+    fn test_shortest_plan() {
+        let dial = Dial::default();
+        let positions = [0, 37, 12];
+        let rotations = plan(&dial, &positions, None).unwrap();
+
+        assert_eq!(replay(&dial, &positions, &rotations), (3, 1, 50 + 37 + 25));
+        assert_eq!(
+            rotations[1..],
+            ["R37".parse().unwrap(), "L25".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_plan_with_crossings() {
+        let dial = Dial::default();
+        let positions = [0, 37];
+
+        let rotations = plan(&dial, &positions, Some(2)).unwrap();
+        // Cheapest extra crossing: step back over zero once before heading to 37
+        assert_eq!(replay(&dial, &positions, &rotations), (2, 2, 89));
+
+        let rotations = plan(&dial, &positions, Some(3)).unwrap();
+        assert_eq!(replay(&dial, &positions, &rotations).1, 3);
+
+        // Stopping on zero already counts as passing it
+        assert!(plan(&dial, &positions, Some(0)).is_none());
+    }
+}
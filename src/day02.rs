@@ -1,98 +1,323 @@
-use crate::utils::{print_day_header, print_result, print_section, read_input};
+use std::{ops::RangeInclusive, str::FromStr};
 
-const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,
+use crate::cli::Args;
+use crate::day02_catalogue::day02_catalogue;
+use crate::day02_predicate::{Predicate, day02_predicate};
+use crate::parsing::Block;
+use crate::range_set::{Range, RangeSet};
+use crate::utils::{print_day_header, print_result, print_section, read_input_from};
+
+const DEFAULT_RADIX: u32 = 10;
+
+pub(crate) const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,
 1698522-1698528,446443-446449,38593856-38593862,565653-565659,
 824824821-824824827,2121212118-2121212124";
 
-#[derive(Debug, Copy, Clone)]
-struct Range {
-    start: u64,
-    end: u64,
-}
-
-fn parse_range(s: &str) -> Result<Range, String> {
-    let (raw_start, raw_end) = s
-        .split_once('-')
-        .ok_or_else(|| format!("Not a valid range: {}", s))?;
-
-    Ok(Range {
-        start: raw_start
-            .trim()
-            .parse()
-            .map_err(|_| format!("Not a valid number: {}", raw_start))?,
-        end: raw_end
-            .trim()
-            .parse()
-            .map_err(|_| format!("Not a valid number: {}", raw_end))?,
-    })
+type Solver = fn(&[Range], u32) -> u128;
+
+pub(crate) fn parse(raw_data: &str) -> Vec<Range> {
+    Block::new(raw_data)
+        .parse_list(',')
+        .unwrap_or_else(|e| panic!("Failed to parse ranges: {}", e))
 }
 
-fn parse(raw_data: &str) -> Vec<Range> {
-    raw_data
-        .split(',')
-        .map(|s| parse_range(s).expect("Failed to parse range"))
-        .collect()
+/// How IDs covered by several ranges are counted.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub(crate) enum Counting {
+    /// Overlapping ranges are merged first, so every ID counts once
+    #[default]
+    Once,
+    /// Every range is summed on its own, so an ID counts once per range covering it
+    PerRange,
 }
 
-fn is_mirror_password(password: u64) -> bool {
-    let password_string = password.to_string();
-    let len = password_string.len();
+impl FromStr for Counting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(Counting::Once),
+            "per-range" => Ok(Counting::PerRange),
+            _ => Err(format!(
+                "Invalid counting mode: {} (expected once or per-range)",
+                s
+            )),
+        }
+    }
+}
 
-    if !len.is_multiple_of(2) {
-        return false;
+/// Rejects reversed ranges, which would otherwise be silently empty, and merges the overlapping
+/// ones when every ID should count once.
+pub(crate) fn normalise(data: Vec<Range>, counting: Counting) -> Result<Vec<Range>, String> {
+    if let Some(range) = data.iter().find(|range| range.is_empty()) {
+        return Err(format!("Range {}-{} is reversed", range.start, range.end));
     }
 
-    let (left, right) = password_string.split_at(len / 2);
-    left == right
+    Ok(match counting {
+        Counting::Once => data
+            .into_iter()
+            .collect::<RangeSet>()
+            .iter()
+            .copied()
+            .collect(),
+        Counting::PerRange => data,
+    })
+}
+
+/// Parsed and normalised ranges, panicking on invalid ones like `parse`.
+pub(crate) fn parse_normalised(raw_data: &str, counting: Counting) -> Vec<Range> {
+    normalise(parse(raw_data), counting).unwrap_or_else(|e| panic!("Invalid ranges: {}", e))
+}
+
+/// Number of digits of `n` written in `radix`.
+pub(crate) fn digit_count(n: u64, radix: u32) -> u32 {
+    n.checked_ilog(u64::from(radix)).unwrap_or(0) + 1
+}
+
+/// `1 0..0 1 0..0 1` in `radix`: multiplying a `period`-digit block by it repeats the block until
+/// the number is `len` digits long.
+pub(crate) fn repunit(radix: u32, len: u32, period: u32) -> u128 {
+    let radix = u128::from(radix);
+    (radix.pow(len) - 1) / (radix.pow(period) - 1)
+}
+
+/// Whether the `len` digits of `password` are its last `period` digits repeated, checked with
+/// arithmetic only so no string has to be allocated.
+pub(crate) fn repeats_with_period(password: u64, radix: u32, len: u32, period: u32) -> bool {
+    let password = u128::from(password);
+    let block = password % u128::from(radix).pow(period);
+
+    block * repunit(radix, len, period) == password
+}
+
+pub(crate) fn is_mirror_password(password: u64, radix: u32) -> bool {
+    let len = digit_count(password, radix);
+    len.is_multiple_of(2) && repeats_with_period(password, radix, len, len / 2)
 }
 
-fn part01(data: &[Range]) -> u64 {
+fn part01_enumerated(data: &[Range], radix: u32) -> u128 {
     data.iter()
         .flat_map(|range| range.start..=range.end)
-        .filter(|&password| is_mirror_password(password))
+        .filter(|&password| is_mirror_password(password, radix))
+        .map(u128::from)
         .sum()
 }
 
-fn has_repeating_pattern(password: u64) -> bool {
-    let password_string = password.to_string();
-    let len = password_string.len();
-
-    (1..len).any(|split_size| {
-        if !len.is_multiple_of(split_size) {
-            return false;
-        }
+/// Length of the shortest block `password` is made of, if it's repeated at least twice.
+pub(crate) fn smallest_period(password: u64, radix: u32) -> Option<u32> {
+    let len = digit_count(password, radix);
 
-        let first_chunk = &password_string[..split_size];
+    (1..len)
+        .filter(|&period| len.is_multiple_of(period))
+        .find(|&period| repeats_with_period(password, radix, len, period))
+}
 
-        password_string
-            .as_bytes()
-            .chunks(split_size)
-            .all(|chunk| chunk == first_chunk.as_bytes())
-    })
+fn has_repeating_pattern(password: u64, radix: u32) -> bool {
+    smallest_period(password, radix).is_some()
 }
 
-fn part02(data: &[Range]) -> u64 {
+fn part02_enumerated(data: &[Range], radix: u32) -> u128 {
     data.iter()
         .flat_map(|range| range.start..=range.end)
-        .filter(|&password| has_repeating_pattern(password))
+        .filter(|&password| has_repeating_pattern(password, radix))
+        .map(u128::from)
+        .sum()
+}
+
+/// Blocks of `period` digits that, repeated to `len` digits, give a number within `range`.
+pub(crate) fn block_bounds(
+    range: &Range,
+    radix: u32,
+    len: u32,
+    period: u32,
+) -> RangeInclusive<u128> {
+    let repunit = repunit(radix, len, period);
+    let radix = u128::from(radix);
+
+    // Blocks can't start with a zero, otherwise the number would be shorter
+    let lowest_block = radix
+        .pow(period - 1)
+        .max(u128::from(range.start).div_ceil(repunit));
+    let highest_block = (radix.pow(period) - 1).min(u128::from(range.end) / repunit);
+
+    lowest_block..=highest_block
+}
+
+/// Sum of the `len`-digit numbers in `range` made of a `period`-digit block repeated. They are
+/// `block * repunit`, so only the valid blocks need counting.
+fn sum_with_period(range: &Range, radix: u32, len: u32, period: u32) -> u128 {
+    let blocks = block_bounds(range, radix, len, period);
+    if blocks.is_empty() {
+        return 0;
+    }
+
+    let (lowest_block, highest_block) = blocks.into_inner();
+    let n_blocks = highest_block - lowest_block + 1;
+    let blocks_sum = if n_blocks.is_multiple_of(2) {
+        n_blocks / 2 * (lowest_block + highest_block)
+    } else {
+        (lowest_block + highest_block) / 2 * n_blocks
+    };
+
+    blocks_sum * repunit(radix, len, period)
+}
+
+pub(crate) fn digit_lengths(range: &Range, radix: u32) -> RangeInclusive<u32> {
+    digit_count(range.start, radix)..=digit_count(range.end, radix)
+}
+
+/// Same as `part01_enumerated`, but in time independent of the width of the ranges.
+fn part01(data: &[Range], radix: u32) -> u128 {
+    data.iter()
+        .flat_map(|range| {
+            digit_lengths(range, radix)
+                .filter(|len| len.is_multiple_of(2))
+                .map(move |len| sum_with_period(range, radix, len, len / 2))
+        })
         .sum()
 }
 
-pub fn day02() {
+/// Numbers with several periods (`222222` has 1, 2 and 3) are counted once, under their smallest
+/// one: every period's sum has the sums of the smaller periods dividing it taken out.
+fn sum_repeating(range: &Range, radix: u32, len: u32) -> u128 {
+    let periods: Vec<u32> = (1..len).filter(|&p| len.is_multiple_of(p)).collect();
+    let mut smallest_period_sums: Vec<u128> = Vec::with_capacity(periods.len());
+
+    for &period in &periods {
+        let already_counted: u128 = periods
+            .iter()
+            .zip(&smallest_period_sums)
+            .filter(|&(&p, _)| period.is_multiple_of(p))
+            .map(|(_, &sum)| sum)
+            .sum();
+
+        smallest_period_sums.push(sum_with_period(range, radix, len, period) - already_counted);
+    }
+
+    smallest_period_sums.iter().sum()
+}
+
+/// Same as `part02_enumerated`, but in time independent of the width of the ranges.
+fn part02(data: &[Range], radix: u32) -> u128 {
+    data.iter()
+        .flat_map(|range| {
+            digit_lengths(range, radix).map(move |len| sum_repeating(range, radix, len))
+        })
+        .sum()
+}
+
+/// `--radix`, checked to be a base digits can be written in.
+pub(crate) fn radix_from_args(args: &Args) -> u32 {
+    let radix = args.get("radix").unwrap_or(DEFAULT_RADIX);
+    if !(2..=36).contains(&radix) {
+        panic!("Radix must be between 2 and 36, got {}", radix);
+    }
+
+    radix
+}
+
+pub fn day02(args: &Args) {
+    // IDs are always written in decimal, `--radix` only changes the base their digits are read in
+    let radix = radix_from_args(args);
+    // `--count per-range` sums overlapping ranges separately, counting their shared IDs again
+    let counting = args.get("count").unwrap_or_default();
+
+    // `--catalogue <format>` lists the matching IDs of the actual input instead, so it can be redirected
+    if let Some(format) = args.get("catalogue") {
+        return day02_catalogue(args, radix, counting, format);
+    }
+
+    // `--predicate <expr>` sums the IDs matching a custom rule instead of the two parts
+    if let Some(predicate) = args.get::<Predicate>("predicate") {
+        return day02_predicate(args, radix, counting, &predicate);
+    }
+
     print_day_header(2);
 
-    let example_data = parse(EXAMPLE);
-    let actual_data = parse(&read_input(2));
+    // `--enumerate` checks every number one by one instead, which is only viable for small ranges
+    let (part01, part02): (Solver, Solver) = if args.flag("enumerate") {
+        (part01_enumerated, part02_enumerated)
+    } else {
+        (part01, part02)
+    };
+
+    let example_data = parse_normalised(EXAMPLE, counting);
+    let actual_data = parse_normalised(&read_input_from(args, 2), counting);
 
     print_section("Part 1 (Example)");
-    print_result("Sum of mirror passwords", part01(&example_data));
+    print_result("Sum of mirror passwords", part01(&example_data, radix));
 
     print_section("Part 1 (Actual)");
-    print_result("Sum of mirror passwords", part01(&actual_data));
+    print_result("Sum of mirror passwords", part01(&actual_data, radix));
 
     print_section("Part 2 (Example)");
-    print_result("Sum of repeating pattern passwords", part02(&example_data));
+    print_result(
+        "Sum of repeating pattern passwords",
+        part02(&example_data, radix),
+    );
 
     print_section("Part 2 (Actual)");
-    print_result("Sum of repeating pattern passwords", part02(&actual_data));
+    print_result(
+        "Sum of repeating pattern passwords",
+        part02(&actual_data, radix),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closed_form_matches_enumeration() {
+        let example = parse(EXAMPLE);
+        assert_eq!(part01(&example, 10), 1227775554);
+        assert_eq!(part02(&example, 10), 4174379265);
+
+        let ranges: Vec<Range> = [(0, 1), (1, 9), (5, 5000), (98, 123456), (1010, 1010)]
+            .into_iter()
+            .map(|(start, end)| Range::new(start, end))
+            .collect();
+
+        for radix in [2, 3, 10, 16, 36] {
+            for range in ranges.chunks(1) {
+                assert_eq!(part01(range, radix), part01_enumerated(range, radix));
+                assert_eq!(part02(range, radix), part02_enumerated(range, radix));
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_radixes() {
+        // 0b1010 and 0xabab
+        assert!(is_mirror_password(10, 2) && !is_mirror_password(10, 10));
+        assert!(is_mirror_password(0xabab, 16));
+        // 0b111 only repeats with a period of 1
+        assert!(has_repeating_pattern(7, 2) && !is_mirror_password(7, 2));
+        assert!(has_repeating_pattern(u64::MAX, 2) && has_repeating_pattern(u64::MAX, 16));
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        let data = parse("11-22,15-33,22-22,95-115");
+
+        let once = normalise(data.clone(), Counting::Once).unwrap();
+        assert_eq!(once, vec![Range::new(11, 33), Range::new(95, 115)]);
+        assert_eq!(part02(&once, 10), 11 + 22 + 33 + 99 + 111);
+
+        let per_range = normalise(data, Counting::PerRange).unwrap();
+        assert_eq!(part02(&per_range, 10), 11 + 22 + 22 + 33 + 22 + 99 + 111);
+
+        let error = normalise(parse("11-22,115-95"), Counting::PerRange).unwrap_err();
+        assert!(error.contains("115-95"));
+    }
+
+    #[test]
+    fn test_huge_ranges() {
+        let everything = [Range::new(0, u64::MAX)];
+
+        // 11 + 22 + ... + 99
+        assert_eq!(part01(&[Range::new(10, 99)], 10), 495);
+        assert!(part02(&everything, 10) > part01(&everything, 10));
+        assert!(part02(&everything, 2) > part01(&everything, 2));
+    }
 }
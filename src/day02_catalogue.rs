@@ -0,0 +1,250 @@
+/// Every ID matching day 2's part 2, listed with the range it comes from and the block it repeats,
+/// to audit which ranges contribute most to the totals.
+use std::str::FromStr;
+
+use crate::cli::Args;
+use crate::day02::{
+    Counting, block_bounds, digit_count, digit_lengths, is_mirror_password, parse_normalised,
+    repunit, smallest_period,
+};
+use crate::range_set::{Range, RangeSet};
+use crate::utils::read_input_from;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    id: u64,
+    /// Shortest repeated block, written in the radix the IDs were checked in
+    block: String,
+    repetitions: u32,
+    /// Whether the ID also counts for part 1
+    mirror: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RangeEntries {
+    range: Range,
+    entries: Vec<Entry>,
+}
+
+impl RangeEntries {
+    fn total(&self) -> u128 {
+        self.entries.iter().map(|entry| u128::from(entry.id)).sum()
+    }
+}
+
+fn to_radix_string(mut n: u64, radix: u32) -> String {
+    let mut digits = Vec::new();
+
+    loop {
+        digits.push(char::from_digit((n % u64::from(radix)) as u32, radix).unwrap());
+        n /= u64::from(radix);
+
+        if n == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().collect()
+}
+
+/// Matching IDs of `range` in increasing order, built from their blocks rather than by checking
+/// every number of the range.
+fn matching_ids(range: &Range, radix: u32) -> Vec<u64> {
+    let mut ids = Vec::new();
+
+    for len in digit_lengths(range, radix) {
+        for period in (1..len).filter(|&p| len.is_multiple_of(p)) {
+            let repunit = repunit(radix, len, period);
+
+            ids.extend(
+                block_bounds(range, radix, len, period)
+                    .map(|block| (block * repunit) as u64)
+                    // Longer periods also produce the IDs of the shorter ones dividing them
+                    .filter(|&id| smallest_period(id, radix) == Some(period)),
+            );
+        }
+    }
+
+    ids.sort_unstable();
+    ids
+}
+
+/// Entries of every input range, in input order. When counting once, an ID covered by several
+/// ranges is only listed under the first of them, so the totals match the merged ranges.
+fn catalogue(data: &[Range], radix: u32, counting: Counting) -> Vec<RangeEntries> {
+    let mut listed = RangeSet::new();
+    let mut groups = Vec::with_capacity(data.len());
+
+    for range in data {
+        let entries = matching_ids(range, radix)
+            .into_iter()
+            .filter(|&id| counting == Counting::PerRange || !listed.contains(id))
+            .map(|id| {
+                let period = smallest_period(id, radix).unwrap();
+                let len = digit_count(id, radix);
+
+                Entry {
+                    id,
+                    block: to_radix_string(id / u64::from(radix).pow(len - period), radix),
+                    repetitions: len / period,
+                    mirror: is_mirror_password(id, radix),
+                }
+            })
+            .collect();
+
+        listed.insert(*range);
+        groups.push(RangeEntries {
+            range: *range,
+            entries,
+        });
+    }
+
+    groups
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CatalogueFormat {
+    Table,
+    Csv,
+}
+
+impl FromStr for CatalogueFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(CatalogueFormat::Table),
+            "csv" => Ok(CatalogueFormat::Csv),
+            _ => Err(format!(
+                "Invalid catalogue format: {} (expected table or csv)",
+                s
+            )),
+        }
+    }
+}
+
+fn render_catalogue(groups: &[RangeEntries], format: CatalogueFormat) -> String {
+    let mut out = String::new();
+
+    match format {
+        CatalogueFormat::Table => {
+            for group in groups {
+                out.push_str(&format!(
+                    "{}-{}: {} IDs, sum {}\n",
+                    group.range.start,
+                    group.range.end,
+                    group.entries.len(),
+                    group.total()
+                ));
+
+                if group.entries.is_empty() {
+                    continue;
+                }
+
+                out.push_str("                    id                 block  repetitions  mirror\n");
+                for entry in &group.entries {
+                    out.push_str(&format!(
+                        "  {:>20}  {:>20}  {:>11}  {:>6}\n",
+                        entry.id,
+                        entry.block,
+                        entry.repetitions,
+                        if entry.mirror { "yes" } else { "no" }
+                    ));
+                }
+            }
+
+            out.push_str(&format!(
+                "Total: {} IDs, sum {}\n",
+                groups
+                    .iter()
+                    .map(|group| group.entries.len())
+                    .sum::<usize>(),
+                groups.iter().map(RangeEntries::total).sum::<u128>()
+            ));
+        }
+        CatalogueFormat::Csv => {
+            out.push_str("range_start,range_end,id,block,repetitions,mirror\n");
+            for group in groups {
+                for entry in &group.entries {
+                    out.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        group.range.start,
+                        group.range.end,
+                        entry.id,
+                        entry.block,
+                        entry.repetitions,
+                        entry.mirror
+                    ));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+pub fn day02_catalogue(args: &Args, radix: u32, counting: Counting, format: CatalogueFormat) {
+    // Grouped by the ranges as written, merging them would list IDs under ranges not in the input
+    let data = parse_normalised(&read_input_from(args, 2), Counting::PerRange);
+    print!("{}", render_catalogue(&catalogue(&data, radix, counting), format));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day02::parse;
+
+    #[test]
+    fn test_catalogue_matches_predicates() {
+        let data = parse("11-22,95-115,998-1012,222220-222224,2121212118-2121212124");
+        let groups = catalogue(&data, 10, Counting::Once);
+
+        for group in &groups {
+            let expected: Vec<u64> = (group.range.start..=group.range.end)
+                .filter(|&id| smallest_period(id, 10).is_some())
+                .collect();
+            let ids: Vec<u64> = group.entries.iter().map(|entry| entry.id).collect();
+            assert_eq!(ids, expected);
+        }
+
+        let entry = &groups[3].entries[0];
+        assert_eq!((entry.id, entry.block.as_str()), (222222, "2"));
+        assert_eq!((entry.repetitions, entry.mirror), (6, true));
+        assert_eq!(groups[4].entries[0].block, "21");
+
+        // 0b1010 and 0b1111 in 10-15
+        let binary = catalogue(&[Range::new(10, 15)], 2, Counting::Once);
+        assert_eq!(binary[0].entries[0].block, "10");
+        assert_eq!(binary[0].entries.len(), 2);
+
+        let csv = render_catalogue(&groups, CatalogueFormat::Csv);
+        assert!(csv.contains("\n11,22,22,2,2,true\n"));
+    }
+
+    #[test]
+    fn test_overlapping_ranges_keep_their_groups() {
+        let data = parse("10-40,30-60,61-70");
+        let ranges = |groups: &[RangeEntries]| -> Vec<(u64, u64, Vec<u64>)> {
+            groups
+                .iter()
+                .map(|group| {
+                    let ids = group.entries.iter().map(|entry| entry.id).collect();
+                    (group.range.start, group.range.end, ids)
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            ranges(&catalogue(&data, 10, Counting::Once)),
+            vec![
+                (10, 40, vec![11, 22, 33]),
+                (30, 60, vec![44, 55]),
+                (61, 70, vec![66]),
+            ]
+        );
+        assert_eq!(
+            ranges(&catalogue(&data, 10, Counting::PerRange))[1],
+            (30, 60, vec![33, 44, 55])
+        );
+    }
+}
@@ -0,0 +1,196 @@
+/// Custom rules for which IDs are invalid, e.g. `--predicate "repeats=3 or palindrome and period=2"`.
+/// Part 1 is `repeats=2` and part 2 is `repeats>=2`.
+use std::{fmt::Display, str::FromStr};
+
+use crate::cli::Args;
+use crate::day02::{Counting, EXAMPLE, digit_count, parse_normalised, repeats_with_period};
+use crate::range_set::Range;
+use crate::utils::{print_day_header, print_result, print_section, read_input_from};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// `repeats=k`: the ID splits into exactly `k` equal blocks
+    Repeats(u32),
+    /// `repeats>=k`: the ID splits into `k` or more equal blocks
+    RepeatsAtLeast(u32),
+    /// `period=p`: the ID is a block of `p` digits repeated at least twice
+    Period(u32),
+    /// `palindrome`: the ID reads the same backwards
+    Palindrome,
+    /// Terms joined by `and`, which binds tighter than `or`
+    All(Vec<Predicate>),
+    /// Terms joined by `or`
+    Any(Vec<Predicate>),
+}
+
+impl Predicate {
+    pub fn matches(&self, id: u64, radix: u32) -> bool {
+        let len = digit_count(id, radix);
+
+        match self {
+            Predicate::Repeats(k) => {
+                len.is_multiple_of(*k) && repeats_with_period(id, radix, len, len / k)
+            }
+            Predicate::RepeatsAtLeast(k) => (*k..=len)
+                .filter(|&k| len.is_multiple_of(k))
+                .any(|k| repeats_with_period(id, radix, len, len / k)),
+            Predicate::Period(p) => {
+                *p < len && len.is_multiple_of(*p) && repeats_with_period(id, radix, len, *p)
+            }
+            Predicate::Palindrome => reversed(id, radix) == u128::from(id),
+            Predicate::All(terms) => terms.iter().all(|term| term.matches(id, radix)),
+            Predicate::Any(terms) => terms.iter().any(|term| term.matches(id, radix)),
+        }
+    }
+}
+
+/// `id` with its digits in `radix` reversed, in a `u128` since it may not fit back in a `u64`.
+fn reversed(mut id: u64, radix: u32) -> u128 {
+    let mut reversed = 0;
+
+    loop {
+        reversed = reversed * u128::from(radix) + u128::from(id % u64::from(radix));
+        id /= u64::from(radix);
+
+        if id == 0 {
+            return reversed;
+        }
+    }
+}
+
+fn parse_term(s: &str) -> Result<Predicate, String> {
+    let count = |raw: &str| match raw.parse::<u32>() {
+        Ok(0) | Err(_) => Err(format!(
+            "Invalid count in {:?}: expected a positive number",
+            s
+        )),
+        Ok(n) => Ok(n),
+    };
+
+    if s == "palindrome" {
+        Ok(Predicate::Palindrome)
+    } else if let Some(raw) = s.strip_prefix("repeats>=") {
+        count(raw).map(Predicate::RepeatsAtLeast)
+    } else if let Some(raw) = s.strip_prefix("repeats=") {
+        count(raw).map(Predicate::Repeats)
+    } else if let Some(raw) = s.strip_prefix("period=") {
+        count(raw).map(Predicate::Period)
+    } else {
+        Err(format!(
+            "Invalid predicate: {} (expected repeats=k, repeats>=k, period=p or palindrome)",
+            s
+        ))
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+
+        let mut alternatives = tokens
+            .split(|&token| token == "or")
+            .map(|conjunction| {
+                let mut terms = conjunction
+                    .split(|&token| token == "and")
+                    .map(|term| match term {
+                        [term] => parse_term(term),
+                        [] => Err(format!("Missing term in predicate {:?}", s)),
+                        _ => Err(format!("Missing and/or between {}", term.join(" "))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(match terms.len() {
+                    1 => terms.remove(0),
+                    _ => Predicate::All(terms),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => Predicate::Any(alternatives),
+        })
+    }
+}
+
+impl Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |terms: &[Predicate], separator: &str| {
+            terms
+                .iter()
+                .map(|term| term.to_string())
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+
+        match self {
+            Predicate::Repeats(k) => write!(f, "repeats={}", k),
+            Predicate::RepeatsAtLeast(k) => write!(f, "repeats>={}", k),
+            Predicate::Period(p) => write!(f, "period={}", p),
+            Predicate::Palindrome => write!(f, "palindrome"),
+            Predicate::All(terms) => write!(f, "{}", join(terms, " and ")),
+            Predicate::Any(terms) => write!(f, "{}", join(terms, " or ")),
+        }
+    }
+}
+
+/// Number and sum of the IDs matching `predicate`, checking every ID of the ranges one by one.
+fn count_and_sum(data: &[Range], predicate: &Predicate, radix: u32) -> (usize, u128) {
+    data.iter()
+        .flat_map(|range| range.start..=range.end)
+        .filter(|&id| predicate.matches(id, radix))
+        .fold((0, 0), |(count, sum), id| (count + 1, sum + u128::from(id)))
+}
+
+pub fn day02_predicate(args: &Args, radix: u32, counting: Counting, predicate: &Predicate) {
+    print_day_header(2);
+
+    let example_data = parse_normalised(EXAMPLE, counting);
+    let actual_data = parse_normalised(&read_input_from(args, 2), counting);
+
+    for (section, data) in [("Example", example_data), ("Actual", actual_data)] {
+        let (count, sum) = count_and_sum(&data, predicate, radix);
+
+        print_section(&format!("{} ({})", predicate, section));
+        print_result("Matching IDs", count);
+        print_result("Sum of matching IDs", sum);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predicates() {
+        let example = parse_normalised(EXAMPLE, Counting::Once);
+        let sum = |raw: &str| count_and_sum(&example, &raw.parse().unwrap(), 10).1;
+
+        assert_eq!(sum("repeats=2"), 1227775554);
+        assert_eq!(sum("repeats>=2"), 4174379265);
+        assert_eq!(sum("repeats=2 or repeats>=3"), 4174379265);
+        // 222222 is the only one splitting in both 2 and 3 blocks
+        assert_eq!(sum("repeats=2 and repeats=3"), 222222);
+
+        let matches = |raw: &str, id| raw.parse::<Predicate>().unwrap().matches(id, 10);
+        assert!(matches("period=2", 121212) && !matches("period=2", 121213));
+        assert!(matches("palindrome", 12321) && !matches("palindrome", 123));
+        assert!(matches("palindrome and period=1", 777));
+        // `and` binds tighter: (repeats=2 and palindrome) or period=3
+        assert!(matches("repeats=2 and palindrome or period=3", 123123));
+        assert!(!matches("repeats=2 and palindrome or period=3", 1212));
+        assert!(Predicate::Palindrome.matches(0b1001, 2));
+    }
+
+    #[test]
+    fn test_parse_predicates() {
+        let raw = "repeats>=3 or palindrome and period=2";
+        assert_eq!(raw.parse::<Predicate>().unwrap().to_string(), raw);
+
+        assert!("repeats=0".parse::<Predicate>().is_err());
+        assert!("palindrome or".parse::<Predicate>().is_err());
+        assert!("palindrome period=2".parse::<Predicate>().is_err());
+    }
+}
@@ -1,4 +1,11 @@
-use crate::utils::{print_day_header, print_result, print_section, read_input};
+use crate::big_decimal::BigDecimal;
+use crate::cli::Args;
+use crate::day03_strategy::Strategy;
+use crate::overflow::{Overflow, OverflowError};
+use crate::parsing::Block;
+use crate::utils::{
+    print_checked_result, print_day_header, print_result, print_section, read_input,
+};
 
 const EXAMPLE: &str = "987654321111111
 811111111111119
@@ -6,22 +13,128 @@ const EXAMPLE: &str = "987654321111111
 818181911112111";
 const PART2_BATTERY_COUNT: usize = 12;
 
-type Bank = Vec<u32>;
+pub(crate) type Bank = Vec<u32>;
 type BatteryMatrix = Vec<Bank>;
 
-fn parse(raw_data: &str) -> BatteryMatrix {
-    raw_data
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| c.to_digit(10).expect("Not parseable"))
-                .collect()
+/// Batteries switched on in a bank, from left to right.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Selection {
+    pub(crate) indices: Vec<usize>,
+    pub(crate) digits: Vec<u32>,
+}
+
+impl Selection {
+    pub(crate) fn new(bank: &Bank, indices: Vec<usize>) -> Self {
+        let digits = indices.iter().map(|&idx| bank[idx]).collect();
+        Selection { indices, digits }
+    }
+
+    /// The selected batteries as the digits of a number in `radix`.
+    fn joltage(&self, radix: u32, overflow: Overflow) -> Result<u128, OverflowError> {
+        self.digits.iter().try_fold(0, |acc, &digit| {
+            overflow.add(overflow.mul(acc, radix as u128)?, digit as u128)
         })
-        .collect()
+    }
+
+    /// Exact joltage, however many batteries were selected.
+    fn exact_joltage(&self, radix: u32) -> BigDecimal {
+        BigDecimal::from_digits(self.digits.iter().copied(), radix)
+    }
+
+    /// The whole bank with the selected batteries highlighted.
+    fn render(&self, bank: &Bank, format: &CellFormat) -> String {
+        let mut selected = self.indices.iter().peekable();
+
+        let cells: Vec<String> = bank
+            .iter()
+            .enumerate()
+            .map(|(idx, &cell)| match selected.next_if_eq(&&idx) {
+                Some(_) => format!("\x1b[1;32m{}\x1b[0m", format.show(cell)),
+                None => format!("\x1b[2m{}\x1b[0m", format.show(cell)),
+            })
+            .collect();
+
+        cells.join(format.separator())
+    }
+}
+
+/// How the batteries of a bank are written, and the radix their values are combined in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct CellFormat {
+    /// Every battery has to be a digit of this radix
+    radix: u32,
+    /// Batteries are whitespace separated values like `12 7 30 4` instead of single characters
+    separated: bool,
 }
 
-fn highest_jolt(bank: &Bank) -> u32 {
-    let (max_idx, &max) = bank
+impl Default for CellFormat {
+    fn default() -> Self {
+        CellFormat {
+            radix: 10,
+            separated: false,
+        }
+    }
+}
+
+impl CellFormat {
+    /// `--radix` for the digits, e.g. 16 for hex banks, and `--cells` for separated values.
+    fn from_args(args: &Args) -> Result<Self, String> {
+        let format = CellFormat {
+            radix: args.get("radix").unwrap_or(10),
+            separated: args.flag("cells"),
+        };
+
+        // Single characters can only go up to `z`
+        let max_radix = if format.separated { u32::MAX } else { 36 };
+        if !(2..=max_radix).contains(&format.radix) {
+            return Err(format!(
+                "Radix must be between 2 and {}, got {}",
+                max_radix, format.radix
+            ));
+        }
+
+        Ok(format)
+    }
+
+    fn parse(&self, raw_data: &str) -> BatteryMatrix {
+        let block = Block::new(raw_data);
+        let banks = if self.separated {
+            block.parse_lines_with(|line| {
+                line.split_whitespace()
+                    .map(|raw| match raw.parse::<u32>() {
+                        Ok(cell) if cell < self.radix => Ok(cell),
+                        _ => Err(format!("Invalid cell {:?} in radix {}", raw, self.radix)),
+                    })
+                    .collect()
+            })
+        } else {
+            block.parse_chars(|c| {
+                c.to_digit(self.radix)
+                    .ok_or(format!("Not a digit in radix {}", self.radix))
+            })
+        };
+
+        banks.unwrap_or_else(|e| panic!("Failed to parse banks: {}", e))
+    }
+
+    fn show(&self, cell: u32) -> String {
+        match self.separated {
+            true => cell.to_string(),
+            false => char::from_digit(cell, self.radix).unwrap().to_string(),
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        if self.separated { " " } else { "" }
+    }
+}
+
+pub(crate) fn parse(raw_data: &str) -> BatteryMatrix {
+    CellFormat::default().parse(raw_data)
+}
+
+fn highest_jolt(bank: &Bank) -> Selection {
+    let (max_idx, _) = bank
         .iter()
         .enumerate()
         // We can't let it select the last battery otherwise we won't have any options
@@ -31,69 +144,387 @@ fn highest_jolt(bank: &Bank) -> u32 {
         .max_by_key(|(_, value)| *value)
         .expect("Bank must have at least one element");
 
-    let &second_max = bank
+    let (second_max_idx, _) = bank
         .iter()
+        .enumerate()
         // We need to get a battery positioned right to the one we already selected
         .skip(max_idx + 1)
-        .max()
+        .rev()
+        .max_by_key(|(_, value)| *value)
         .expect("Bank must have at least two elements");
 
-    max * 10 + second_max
+    Selection::new(bank, vec![max_idx, second_max_idx])
 }
 
-fn highest_jolt_n(bank: &Bank, count: usize) -> u64 {
-    let mut max_battery_idx = 0;
-    let mut selected_batteries: Vec<u32> = Vec::with_capacity(count);
+/// Indices of the batteries making up the largest `count`-digit joltage, found in one pass: each
+/// battery knocks out the smaller ones picked before it, as long as enough batteries are left to
+/// replace them.
+pub(crate) fn select_batteries(bank: &Bank, count: usize) -> Selection {
+    assert!(count <= bank.len(), "Not enough batteries in bank");
 
-    for n in 1..=count {
-        let space_buffer = count - n;
+    let mut selected: Vec<usize> = Vec::with_capacity(count);
 
-        let (idx, &max) = bank
-            .iter()
-            .enumerate()
-            // We can't select a battery positioned to left of the last selected battery.
-            .skip(max_battery_idx)
-            .rev()
-            // This is necessary so we have space to select all the `n` batteries, otherwise we could eagerly
-            // select a big one near the end and then we wouldn't have enough batteries anymore
-            .skip(space_buffer)
-            .max_by_key(|(_, value)| *value)
-            .expect("Not enough batteries in bank");
+    for (idx, &value) in bank.iter().enumerate() {
+        let remaining = bank.len() - idx;
+
+        // Ties keep the earlier battery, so the leftmost of equal choices is picked
+        while let Some(&last) = selected.last() {
+            if bank[last] < value && selected.len() - 1 + remaining >= count {
+                selected.pop();
+            } else {
+                break;
+            }
+        }
 
-        max_battery_idx = idx + 1;
-        selected_batteries.push(max);
+        if selected.len() < count {
+            selected.push(idx);
+        }
     }
 
-    selected_batteries
-        .iter()
-        .fold(0, |acc, &digit| (acc * 10) + (digit as u64))
+    Selection::new(bank, selected)
+}
+
+fn part01(data: &BatteryMatrix, radix: u32) -> u128 {
+    data.iter()
+        // Two digits always fit
+        .map(|bank| {
+            highest_jolt(bank)
+                .joltage(radix, Overflow::Unchecked)
+                .unwrap()
+        })
+        .sum()
 }
 
-fn part01(data: &BatteryMatrix) -> u32 {
-    data.iter().map(highest_jolt).sum()
+/// `--highlight[=count]` shows which batteries are switched on in every bank, for part 2's count
+/// unless another one is given.
+fn print_selections(
+    data: &BatteryMatrix,
+    format: &CellFormat,
+    selections: Result<Vec<Selection>, String>,
+) {
+    let selections = match selections {
+        Ok(selections) => selections,
+        Err(e) => return print_checked_result("No selection", Err::<String, _>(e)),
+    };
+
+    for (bank, selection) in data.iter().zip(selections) {
+        println!(
+            "  {}  \x1b[1m{}\x1b[0m",
+            selection.render(bank, format),
+            selection
+                .digits
+                .iter()
+                .map(|&cell| format.show(cell))
+                .collect::<Vec<_>>()
+                .join(format.separator())
+        );
+    }
+}
+
+/// Checks every bank has enough batteries to switch `count` of them on.
+fn check_battery_count(data: &BatteryMatrix, count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Err("At least one battery must be switched on".to_string());
+    }
+
+    match data.iter().position(|bank| bank.len() < count) {
+        Some(i) => Err(format!(
+            "Bank {} only has {} batteries, {} can't be switched on",
+            i + 1,
+            data[i].len(),
+            count
+        )),
+        None => Ok(()),
+    }
 }
 
-fn part02(data: &BatteryMatrix) -> u64 {
+/// The batteries switched on in every bank, or why they can't all be.
+fn select_all(
+    data: &BatteryMatrix,
+    count: usize,
+    strategy: &Strategy,
+) -> Result<Vec<Selection>, String> {
+    check_battery_count(data, count)?;
+
     data.iter()
-        .map(|bank| highest_jolt_n(bank, PART2_BATTERY_COUNT))
+        .enumerate()
+        .map(|(i, bank)| {
+            strategy.select(bank, count).ok_or_else(|| {
+                format!(
+                    "Bank {} has no way to switch on {} batteries with {}",
+                    i + 1,
+                    count,
+                    strategy
+                )
+            })
+        })
+        .collect()
+}
+
+fn part02(selections: &[Selection], radix: u32, overflow: Overflow) -> Result<u128, OverflowError> {
+    selections.iter().try_fold(0, |acc, selection| {
+        overflow.add(acc, selection.joltage(radix, overflow)?)
+    })
+}
+
+/// Whether the joltages of `banks` banks of `count` batteries written in `radix` are sure to add
+/// up to a `u64`, however large they are.
+fn sum_fits_u64(banks: usize, count: usize, radix: u32) -> bool {
+    u32::try_from(count)
+        .ok()
+        .and_then(|count| u128::from(radix).checked_pow(count))
+        .and_then(|limit| (limit - 1).checked_mul(banks as u128))
+        .is_some_and(|bound| bound <= u128::from(u64::MAX))
+}
+
+/// Same as `part02`, but exact no matter how many batteries are switched on.
+fn part02_exact(selections: &[Selection], radix: u32) -> BigDecimal {
+    selections
+        .iter()
+        .map(|selection| selection.exact_joltage(radix))
         .sum()
 }
 
-pub fn day03() {
+/// Part 2's answer, summed exactly unless it's sure to fit in a `u64`. `overflow` only matters in
+/// that case, so the puzzle's own answers are still checked like the other days.
+fn part02_total(
+    selections: &[Selection],
+    count: usize,
+    radix: u32,
+    overflow: Overflow,
+) -> Result<String, String> {
+    if sum_fits_u64(selections.len(), count, radix) {
+        part02(selections, radix, overflow)
+            .map(|joltage| joltage.to_string())
+            .map_err(|e| e.to_string())
+    } else {
+        Ok(part02_exact(selections, radix).to_string())
+    }
+}
+
+pub fn day03(args: &Args) {
     print_day_header(3);
 
+    let overflow = args.get("overflow").unwrap_or_default();
+    // `--batteries <count>` switches on another number of batteries in part 2, summed exactly
+    // however large it is
+    let count = args.get("batteries").unwrap_or(PART2_BATTERY_COUNT);
+    // `--strategy min,gap=2` and the like choose the batteries differently
+    let strategy: Strategy = args.get("strategy").unwrap_or_default();
+    let solve_part02 = |data: &BatteryMatrix, radix: u32| -> Result<String, String> {
+        let selections = select_all(data, count, &strategy)?;
+        part02_total(&selections, count, radix, overflow)
+    };
+
+    // The example is always written in decimal digits, only the actual input follows `--radix` and `--cells`
+    let example_format = CellFormat::default();
+    let actual_format =
+        CellFormat::from_args(args).unwrap_or_else(|e| panic!("Invalid cell format: {}", e));
+
     let example_data = parse(EXAMPLE);
-    let actual_data = parse(&read_input(3));
+    let actual_data = actual_format.parse(&read_input(3));
+
+    if args.flag("highlight") {
+        let count = args.get("highlight").unwrap_or(count);
+
+        print_section("Selected batteries (Example)");
+        print_selections(
+            &example_data,
+            &example_format,
+            select_all(&example_data, count, &strategy),
+        );
+
+        print_section("Selected batteries (Actual)");
+        print_selections(
+            &actual_data,
+            &actual_format,
+            select_all(&actual_data, count, &strategy),
+        );
+        return;
+    }
 
     print_section("Part 1 (Example)");
-    print_result("The total output joltage is: ", part01(&example_data));
+    print_result(
+        "The total output joltage is: ",
+        part01(&example_data, example_format.radix),
+    );
 
     print_section("Part 1 (Actual)");
-    print_result("The total output joltage is: ", part01(&actual_data));
+    print_result(
+        "The total output joltage is: ",
+        part01(&actual_data, actual_format.radix),
+    );
 
     print_section("Part 2 (Example)");
-    print_result("The total output joltage is: ", part02(&example_data));
+    print_checked_result(
+        "The total output joltage is: ",
+        solve_part02(&example_data, example_format.radix),
+    );
 
     print_section("Part 2 (Actual)");
-    print_result("The total output joltage is: ", part02(&actual_data));
+    print_checked_result(
+        "The total output joltage is: ",
+        solve_part02(&actual_data, actual_format.radix),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The original selection, rescanning the rest of the bank for every pick.
+    fn greedy_jolt_n(bank: &Bank, count: usize, overflow: Overflow) -> Result<u128, OverflowError> {
+        let mut max_battery_idx = 0;
+        let mut selected_batteries: Vec<u32> = Vec::with_capacity(count);
+
+        for n in 1..=count {
+            let space_buffer = count - n;
+
+            let (idx, &max) = bank
+                .iter()
+                .enumerate()
+                // We can't select a battery positioned to left of the last selected battery.
+                .skip(max_battery_idx)
+                .rev()
+                // This is necessary so we have space to select all the `n` batteries, otherwise we could eagerly
+                // select a big one near the end and then we wouldn't have enough batteries anymore
+                .skip(space_buffer)
+                .max_by_key(|(_, value)| *value)
+                .expect("Not enough batteries in bank");
+
+            max_battery_idx = idx + 1;
+            selected_batteries.push(max);
+        }
+
+        selected_batteries.iter().try_fold(0, |acc, &digit| {
+            overflow.add(overflow.mul(acc, 10)?, digit as u128)
+        })
+    }
+
+    #[test]
+    fn test_selection_invariants() {
+        let example = parse(EXAMPLE);
+        let selection = highest_jolt(&example[1]);
+        assert_eq!(
+            (selection.indices, selection.digits),
+            (vec![0, 14], vec![8, 9])
+        );
+
+        for bank in &example {
+            for count in 1..=bank.len() {
+                let selection = select_batteries(bank, count);
+
+                assert_eq!(selection.indices.len(), count);
+                assert!(selection.indices.is_sorted_by(|a, b| a < b));
+                assert!(
+                    selection
+                        .indices
+                        .iter()
+                        .zip(&selection.digits)
+                        .all(|(&idx, &digit)| bank[idx] == digit)
+                );
+            }
+
+            assert_eq!(select_batteries(bank, 2), highest_jolt(bank));
+        }
+
+        let rendered =
+            select_batteries(&vec![1, 9, 2], 1).render(&vec![1, 9, 2], &CellFormat::default());
+        assert_eq!(rendered, "\x1b[2m1\x1b[0m\x1b[1;32m9\x1b[0m\x1b[2m2\x1b[0m");
+    }
+
+    #[test]
+    fn test_exact_joltage() {
+        let example = parse(EXAMPLE);
+        let exact = |data: &BatteryMatrix, count| {
+            part02_exact(&select_all(data, count, &Strategy::default()).unwrap(), 10).to_string()
+        };
+
+        assert_eq!(exact(&example, PART2_BATTERY_COUNT), "3121910778619");
+        // Every battery: the banks themselves added up
+        assert_eq!(
+            exact(&example, 15),
+            (987654321111111u128 + 811111111111119 + 234234234234278 + 818181911112111).to_string()
+        );
+
+        let long_bank = vec![parse("9".repeat(60).as_str()).remove(0); 3];
+        assert_eq!(exact(&long_bank, 40), format!("2{}7", "9".repeat(39)));
+
+        let selections = select_all(&long_bank, 40, &Strategy::default()).unwrap();
+        assert!(part02(&selections, 10, Overflow::Widen).is_err());
+        assert!(check_battery_count(&long_bank, 61).is_err());
+
+        assert!(sum_fits_u64(1, 19, 10) && sum_fits_u64(1, 64, 2));
+        assert!(!sum_fits_u64(1, 20, 10) && !sum_fits_u64(2, 64, 2));
+        assert!(!sum_fits_u64(200, PART2_BATTERY_COUNT, 36));
+
+        // Every joltage fits in a u64 but their sum doesn't
+        let selections = select_all(&long_bank, 19, &Strategy::default()).unwrap();
+        assert_eq!(
+            part02_total(&selections, 19, 10, Overflow::Unchecked),
+            Ok(format!("29{}7", "9".repeat(17)))
+        );
+    }
+
+    #[test]
+    fn test_cell_formats() {
+        let cells = CellFormat {
+            radix: 100,
+            separated: true,
+        };
+        let data = cells.parse("12 7 30 4\n99 1 98");
+        assert_eq!(data, vec![vec![12, 7, 30, 4], vec![99, 1, 98]]);
+        // 30 04 and 99 98
+        assert_eq!(part01(&data, cells.radix), 3004 + 9998);
+
+        let selection = select_batteries(&data[0], 3);
+        assert_eq!(selection.digits, vec![12, 30, 4]);
+        assert_eq!(selection.exact_joltage(cells.radix).to_string(), "123004");
+        assert!(
+            selection
+                .render(&data[0], &cells)
+                .contains("\x1b[2m7\x1b[0m \x1b[1;32m30")
+        );
+
+        let hex = CellFormat {
+            radix: 16,
+            separated: false,
+        };
+        let data = hex.parse("1fa3\n9b0c");
+        assert_eq!(part01(&data, hex.radix), 0xfa + 0xbc);
+        assert_eq!(hex.show(select_batteries(&data[1], 1).digits[0]), "c");
+    }
+
+    #[test]
+    fn test_stack_matches_greedy() {
+        let example = parse(EXAMPLE);
+        let selections = select_all(&example, PART2_BATTERY_COUNT, &Strategy::default()).unwrap();
+        assert_eq!(
+            part02(&selections, 10, Overflow::Unchecked),
+            Ok(3121910778619)
+        );
+
+        // Pseudo-random banks with plenty of ties
+        let mut seed: u64 = 42;
+        let mut banks = example;
+        for len in 1..40 {
+            banks.push(
+                (0..len)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        (seed >> 60) as u32 % 10
+                    })
+                    .collect(),
+            );
+        }
+
+        for bank in &banks {
+            for count in 1..=bank.len().min(19) {
+                assert_eq!(
+                    select_batteries(bank, count).joltage(10, Overflow::Unchecked),
+                    greedy_jolt_n(bank, count, Overflow::Unchecked),
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,289 @@
+/// Other ways of switching batteries on than part 2's largest joltage. Once selections constrain
+/// each other the best battery isn't always a safe pick, so every pick also checks that the rest of
+/// the selection can still be completed.
+use std::{fmt::Display, str::FromStr};
+
+use crate::day03::{Bank, Selection, select_batteries};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Objective {
+    #[default]
+    Maximise,
+    Minimise,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Constraint {
+    /// At least this many batteries left off between two selected ones
+    MinGap(usize),
+    /// At most `cap` selected batteries in any `window` consecutive ones
+    WindowCap { window: usize, cap: usize },
+}
+
+/// Comma separated `max` or `min`, followed by any of `no-adjacent`, `gap=<n>` and
+/// `window=<size>:<cap>`, e.g. `min,gap=2`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Strategy {
+    objective: Objective,
+    constraints: Vec<Constraint>,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut strategy = Strategy::default();
+        let number = |raw: &str| {
+            raw.parse::<usize>()
+                .map_err(|e| format!("Invalid number in {}: {}", s, e))
+        };
+
+        for item in s.split(',').map(str::trim) {
+            let constraint = match item {
+                "max" => {
+                    strategy.objective = Objective::Maximise;
+                    continue;
+                }
+                "min" => {
+                    strategy.objective = Objective::Minimise;
+                    continue;
+                }
+                "no-adjacent" => Constraint::MinGap(1),
+                _ => match item.split_once('=') {
+                    Some(("gap", raw)) => Constraint::MinGap(number(raw)?),
+                    Some(("window", raw)) => {
+                        let (window, cap) = raw
+                            .split_once(':')
+                            .ok_or(format!("Invalid window: {} (expected size:cap)", raw))?;
+                        Constraint::WindowCap {
+                            window: number(window)?,
+                            cap: number(cap)?,
+                        }
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Invalid strategy: {} (expected max, min, no-adjacent, gap=<n> or window=<size>:<cap>)",
+                            item
+                        ));
+                    }
+                },
+            };
+
+            match constraint {
+                Constraint::WindowCap { window: 0, .. } | Constraint::WindowCap { cap: 0, .. } => {
+                    return Err(format!(
+                        "Invalid window: {} (size and cap can't be 0)",
+                        item
+                    ));
+                }
+                _ => strategy.constraints.push(constraint),
+            }
+        }
+
+        Ok(strategy)
+    }
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut items = vec![match self.objective {
+            Objective::Maximise => "max".to_string(),
+            Objective::Minimise => "min".to_string(),
+        }];
+
+        items.extend(self.constraints.iter().map(|constraint| match constraint {
+            Constraint::MinGap(gap) => format!("gap={}", gap),
+            Constraint::WindowCap { window, cap } => format!("window={}:{}", window, cap),
+        }));
+
+        write!(f, "{}", items.join(","))
+    }
+}
+
+impl Strategy {
+    /// Whether the battery at `idx` can be switched on after the `picked` ones, in increasing order.
+    fn allows(&self, picked: &[usize], idx: usize) -> bool {
+        self.constraints.iter().all(|constraint| match *constraint {
+            Constraint::MinGap(gap) => picked.last().is_none_or(|&last| idx - last > gap),
+            // The window ending at `idx` is the fullest one it is part of
+            Constraint::WindowCap { window, cap } => {
+                picked
+                    .iter()
+                    .rev()
+                    .take_while(|&&last| idx - last < window)
+                    .count()
+                    < cap
+            }
+        })
+    }
+
+    /// Whether `remaining` more batteries can be switched on from `idx` onwards after the `picked`
+    /// ones. Switching each one on as early as allowed fits the most of them. `picked` is left as
+    /// it was.
+    fn can_complete(
+        &self,
+        bank_len: usize,
+        picked: &mut Vec<usize>,
+        idx: usize,
+        remaining: usize,
+    ) -> bool {
+        let before = picked.len();
+
+        for next in idx..bank_len {
+            if picked.len() - before == remaining {
+                break;
+            }
+            if self.allows(picked, next) {
+                picked.push(next);
+            }
+        }
+
+        let complete = picked.len() - before == remaining;
+        picked.truncate(before);
+        complete
+    }
+
+    /// Whether `digit` gives a strictly better joltage than `other` in the same position.
+    fn is_better(&self, digit: u32, other: u32) -> bool {
+        match self.objective {
+            Objective::Maximise => digit > other,
+            Objective::Minimise => digit < other,
+        }
+    }
+
+    /// Best `count` batteries of `bank`, if the constraints leave any way of picking that many.
+    pub fn select(&self, bank: &Bank, count: usize) -> Option<Selection> {
+        if *self == Strategy::default() {
+            return Some(select_batteries(bank, count));
+        }
+
+        let mut picked = Vec::with_capacity(count);
+
+        // Every pick is the best battery that still leaves a way to complete the selection. On a
+        // tie the leftmost one wins like in part 2, as it allows everything the others would.
+        while picked.len() < count {
+            let remaining = count - picked.len();
+            let first = picked.last().map_or(0, |&last| last + 1);
+            let mut best: Option<usize> = None;
+
+            for idx in first..(bank.len() + 1).saturating_sub(remaining) {
+                if best.is_some_and(|best| !self.is_better(bank[idx], bank[best]))
+                    || !self.allows(&picked, idx)
+                {
+                    continue;
+                }
+
+                picked.push(idx);
+                if self.can_complete(bank.len(), &mut picked, idx + 1, remaining - 1) {
+                    best = Some(idx);
+                }
+                picked.pop();
+            }
+
+            picked.push(best?);
+        }
+
+        Some(Selection::new(bank, picked))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day03::parse;
+
+    /// Every subset of `count` batteries that satisfies the constraints, keeping the best digits.
+    fn brute_force(strategy: &Strategy, bank: &Bank, count: usize) -> Option<Vec<u32>> {
+        let valid = |indices: &[usize]| {
+            strategy
+                .constraints
+                .iter()
+                .all(|constraint| match *constraint {
+                    Constraint::MinGap(gap) => {
+                        indices.windows(2).all(|pair| pair[1] - pair[0] > gap)
+                    }
+                    Constraint::WindowCap { window, cap } => (0..bank.len()).all(|start| {
+                        indices
+                            .iter()
+                            .filter(|&&idx| (start..start + window).contains(&idx))
+                            .count()
+                            <= cap
+                    }),
+                })
+        };
+
+        (0u32..1 << bank.len())
+            .filter(|subset| subset.count_ones() as usize == count)
+            .map(|subset| {
+                (0..bank.len())
+                    .filter(|&i| subset & (1 << i) != 0)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|indices| valid(indices))
+            .map(|indices| indices.iter().map(|&i| bank[i]).collect::<Vec<_>>())
+            .reduce(|best, digits| match strategy.objective {
+                Objective::Maximise => best.max(digits),
+                Objective::Minimise => best.min(digits),
+            })
+    }
+
+    #[test]
+    fn test_selection_matches_brute_force() {
+        let banks = parse("9192837465\n1111199999\n5273\n8181819111\n3141592653");
+        let strategies = [
+            "max",
+            "min",
+            "no-adjacent",
+            "min,no-adjacent",
+            "gap=2",
+            "window=3:1",
+            "max,window=4:2,gap=1",
+            "min,window=5:3",
+        ];
+
+        for raw in strategies {
+            let strategy: Strategy = raw.parse().unwrap();
+            assert_eq!(strategy.to_string().parse(), Ok(strategy.clone()));
+
+            for bank in &banks {
+                for count in 1..=bank.len() {
+                    let selection = strategy
+                        .select(bank, count)
+                        .map(|selection| selection.digits);
+                    assert_eq!(
+                        selection,
+                        brute_force(&strategy, bank, count),
+                        "{} on {:?}",
+                        raw,
+                        bank
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_constraints() {
+        let bank = parse(&"9182736455463728190".repeat(6)).remove(0);
+
+        for raw in ["window=20:6", "gap=64", "min,window=50:2,gap=3"] {
+            let strategy: Strategy = raw.parse().unwrap();
+            let selection = strategy.select(&bank, 2).unwrap();
+            let indices = &selection.indices;
+
+            assert_eq!(indices.len(), 2);
+            assert!(strategy.allows(&indices[..1], indices[1]));
+        }
+
+        let gap: Strategy = "gap=64".parse().unwrap();
+        assert_eq!(gap.select(&bank, 1).unwrap().digits, vec![9]);
+        assert!(gap.select(&bank, 3).is_none());
+    }
+
+    #[test]
+    fn test_invalid_strategies() {
+        assert!("window=0:1".parse::<Strategy>().is_err());
+        assert!("window=3".parse::<Strategy>().is_err());
+        assert!("fastest".parse::<Strategy>().is_err());
+    }
+}
@@ -1,3 +1,10 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::cli::Args;
+use crate::day04_alt::Positions;
+use crate::day04_rules::Rule;
+use crate::grid::Grid;
+use crate::parsing::Block;
 use crate::utils::{print_day_header, print_result, print_section, read_input};
 
 const EXAMPLE: &str = "
@@ -12,131 +19,494 @@ const EXAMPLE: &str = "
 .@@@@@@@@.
 @.@.@@@.@.";
 
-const ADJACENT_DELTAS: [(i16, i16); 8] = [
-    (-1, 1),
-    (-1, 0),
-    (-1, -1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
-const MAX_NEIGHBORS_TO_BE_CLEANABLE: u8 = 4;
+/// Row and column, signed so neighbours outside the warehouse can be looked up too.
+pub(crate) type Point = (i32, i32);
 
-type Matrix = Vec<Vec<Space>>;
+type Matrix = Grid<Space>;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Space {
+pub(crate) enum Space {
     Paper,
     Empty,
 }
 
-fn parse(raw_data: &str) -> Matrix {
-    raw_data
-        .trim()
-        .lines()
-        .map(|row| {
-            row.chars()
-                .map(|ch| match ch {
-                    '@' => Space::Paper,
-                    '.' => Space::Empty,
-                    _ => panic!("Invalid character in input: '{}'", ch),
-                })
-                .collect()
-        })
-        .collect()
-}
+/// Where the rolls of paper are kept. Backends only store them, the cleaning rules are shared.
+pub(crate) trait Warehouse: Clone {
+    fn from_grid(grid: &Grid<Space>) -> Self;
 
-fn count_paper_neighbours(matrix: &Matrix, row: usize, col: usize) -> u8 {
-    let rows = matrix.len() as i16;
-    let cols = matrix[0].len() as i16;
+    /// Height and width of the grid the warehouse was read from.
+    fn size(&self) -> Point;
 
-    ADJACENT_DELTAS
-        .iter()
-        .filter_map(|(di, dj)| {
-            let i2 = row as i16 + di;
-            let j2 = col as i16 + dj;
-
-            match (i2, j2) {
-                (i2, _) if i2 < 0 || i2 >= rows => None,
-                (_, j2) if j2 < 0 || j2 >= cols => None,
-                (i2, j2) => Some(matrix[i2 as usize][j2 as usize]),
-            }
-        })
-        .filter(|&space| space == Space::Paper)
-        .count() as u8
-}
+    /// Whether there's a roll at `point`, anything outside of the grid being empty.
+    fn has_roll(&self, point: Point) -> bool;
+
+    fn rolls(&self) -> Vec<Point>;
+
+    fn remove(&mut self, point: Point);
 
-fn clean_warehouse(matrix: &Matrix) -> (Matrix, u16) {
-    let mut clean_matrix = matrix.clone();
-    let mut n_cleaned  = 0;
+    fn count_paper_neighbours(&self, point: Point, rule: &Rule) -> usize {
+        rule.neighbours(point, self.size())
+            .filter(|&neighbour| self.has_roll(neighbour))
+            .count()
+    }
+
+    /// Removes every roll the forklift can access, returning how many there were.
+    fn clean(&mut self, rule: &Rule) -> usize {
+        let accessible: Vec<Point> = self
+            .rolls()
+            .into_iter()
+            .filter(|&point| rule.is_accessible(self.count_paper_neighbours(point, rule)))
+            .collect();
 
-    for (i, row) in matrix.iter().enumerate() {
-        for (j, &cell) in row.iter().enumerate() {
-            if cell == Space::Paper {
-                let paper_neighbours = count_paper_neighbours(matrix, i, j);
+        for &point in &accessible {
+            self.remove(point);
+        }
 
-                if paper_neighbours < MAX_NEIGHBORS_TO_BE_CLEANABLE {
-                    clean_matrix[i][j] = Space::Empty;
-                    n_cleaned += 1
+        accessible.len()
+    }
+
+    /// Removes rolls until none is accessible, returning the ones that went in each wave. Neighbour
+    /// counts are computed once and only the neighbours of removed rolls are looked at again, so
+    /// every roll is handled a bounded number of times.
+    fn clean_all(&mut self, rule: &Rule) -> Vec<Vec<Point>> {
+        // Rolls still standing that aren't about to be removed
+        let mut counts: HashMap<Point, usize> = self
+            .rolls()
+            .into_iter()
+            .map(|point| (point, self.count_paper_neighbours(point, rule)))
+            .collect();
+
+        let mut wave: Vec<Point> = counts
+            .iter()
+            .filter(|&(_, &count)| rule.is_accessible(count))
+            .map(|(&point, _)| point)
+            .collect();
+        // Sorted like the following waves, so every backend gives the same answer
+        wave.sort_unstable();
+
+        let mut waves = Vec::new();
+
+        while !wave.is_empty() {
+            for point in &wave {
+                counts.remove(point);
+            }
+
+            let mut touched = Vec::new();
+            for &point in &wave {
+                self.remove(point);
+
+                for neighbour in rule.neighbour_of(point, self.size()) {
+                    if let Some(count) = counts.get_mut(&neighbour) {
+                        *count -= 1;
+                        touched.push(neighbour);
+                    }
                 }
             }
+
+            // Only checked once the whole wave is gone, as the threshold may not be monotonic
+            touched.sort_unstable();
+            touched.dedup();
+
+            let next_wave = touched
+                .into_iter()
+                .filter(|point| rule.is_accessible(counts[point]))
+                .collect();
+            waves.push(wave);
+            wave = next_wave;
         }
+
+        waves
+    }
+}
+
+/// Dense backend: every space of the warehouse, paper or not.
+impl Warehouse for Matrix {
+    fn from_grid(grid: &Grid<Space>) -> Self {
+        grid.clone()
+    }
+
+    fn size(&self) -> Point {
+        (self.height() as i32, self.width() as i32)
     }
 
-    (clean_matrix, n_cleaned)
+    fn has_roll(&self, (i, j): Point) -> bool {
+        i >= 0 && j >= 0 && self.get(i as usize, j as usize) == Some(&Space::Paper)
+    }
+
+    fn rolls(&self) -> Vec<Point> {
+        self.iter()
+            .filter(|&(_, &space)| space == Space::Paper)
+            .map(|((i, j), _)| (i as i32, j as i32))
+            .collect()
+    }
+
+    fn remove(&mut self, (i, j): Point) {
+        self[(i as usize, j as usize)] = Space::Empty;
+    }
 }
 
-fn part01(matrix: &Matrix) -> u16 {
-    clean_warehouse(matrix).1
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+enum Backend {
+    #[default]
+    Dense,
+    Sparse,
 }
 
-fn part02(data: &Matrix) -> u16 {
-    let mut current_matrix = data.clone();
-    let mut total_removed = 0;
+impl FromStr for Backend {
+    type Err = String;
 
-    loop {
-        let (new_matrix, n_removed) = clean_warehouse(&current_matrix);
-        if n_removed == 0 {
-            break;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dense" => Ok(Backend::Dense),
+            "sparse" => Ok(Backend::Sparse),
+            _ => Err(format!("Invalid backend: {} (expected dense or sparse)", s)),
         }
+    }
+}
+
+fn parse<W: Warehouse>(raw_data: &str) -> W {
+    let grid = Block::new(raw_data)
+        .parse_grid(|ch| match ch {
+            '@' => Ok(Space::Paper),
+            '.' => Ok(Space::Empty),
+            _ => Err("Expected '@' or '.'"),
+        })
+        .unwrap_or_else(|e| panic!("Invalid input: {}", e));
+
+    W::from_grid(&grid)
+}
+
+/// When a roll was removed, as seen on the peel-depth map.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Depth {
+    Empty,
+    /// 1-based wave that removed the roll
+    Removed(usize),
+    /// The roll is part of the stable configuration
+    Never,
+}
+
+/// How the warehouse erodes: the wave each roll goes in, and what is left standing at the end.
+struct Peeling<W> {
+    depths: Grid<Depth>,
+    waves: Vec<Vec<Point>>,
+    stable: W,
+}
 
-        total_removed += n_removed;
-        current_matrix = new_matrix;
+fn peel<W: Warehouse>(warehouse: &W, rule: &Rule) -> Peeling<W> {
+    let (height, width) = warehouse.size();
+    let mut depths = Grid::from_rows(vec![vec![Depth::Empty; width as usize]; height as usize])
+        .expect("Rows all have the same width");
+
+    for (i, j) in warehouse.rolls() {
+        depths[(i as usize, j as usize)] = Depth::Never;
+    }
+
+    let mut stable = warehouse.clone();
+    let waves = stable.clean_all(rule);
+
+    for (n, wave) in waves.iter().enumerate() {
+        for &(i, j) in wave {
+            depths[(i as usize, j as usize)] = Depth::Removed(n + 1);
+        }
     }
 
-    total_removed
+    Peeling {
+        depths,
+        waves,
+        stable,
+    }
 }
 
-pub fn day04() {
-    print_day_header(4);
+/// Depth of every roll, `#` for the ones never removed and `.` for empty spaces.
+fn render_depths(depths: &Grid<Depth>) -> String {
+    let cell_width = depths
+        .iter()
+        .filter_map(|(_, &depth)| match depth {
+            Depth::Removed(n) => Some(n.to_string().len()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(1);
+    let separator = if cell_width > 1 { " " } else { "" };
+
+    depths
+        .rows()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|depth| {
+                    let cell = match depth {
+                        Depth::Empty => ".".to_string(),
+                        Depth::Removed(n) => n.to_string(),
+                        Depth::Never => "#".to_string(),
+                    };
+                    format!("{:>width$}", cell, width = cell_width)
+                })
+                .collect();
+
+            format!("  {}\n", cells.join(separator))
+        })
+        .collect()
+}
 
-    let example_data = parse(EXAMPLE);
-    let actual_data = parse(&read_input(4));
+/// Removals per wave, with bars scaled to the largest wave.
+fn render_histogram(waves: &[Vec<Point>]) -> String {
+    const MAX_BAR_WIDTH: usize = 50;
+    let largest = waves.iter().map(Vec::len).max().unwrap_or(0);
+
+    waves
+        .iter()
+        .enumerate()
+        .map(|(n, wave)| {
+            let bar_width = (wave.len() * MAX_BAR_WIDTH).div_ceil(largest);
+            format!(
+                "  {:>4}  {:>6}  {}\n",
+                n + 1,
+                wave.len(),
+                "█".repeat(bar_width)
+            )
+        })
+        .collect()
+}
+
+/// The rolls left standing, in the same format as the puzzle input.
+fn render_warehouse<W: Warehouse>(warehouse: &W) -> String {
+    let (height, width) = warehouse.size();
+
+    (0..height)
+        .map(|i| {
+            let row: String = (0..width)
+                .map(|j| if warehouse.has_roll((i, j)) { '@' } else { '.' })
+                .collect();
+            format!("  {}\n", row)
+        })
+        .collect()
+}
+
+/// `--depths` shows the wave in which every roll is removed, how many go in each wave and the
+/// stable configuration left at the end.
+fn print_peeling<W: Warehouse>(name: &str, warehouse: &W, rule: &Rule) {
+    let peeling = peel(warehouse, rule);
+
+    print_section(&format!("Peel depths ({})", name));
+    print!("{}", render_depths(&peeling.depths));
+
+    print_section(&format!("Removals per wave ({})", name));
+    print!("{}", render_histogram(&peeling.waves));
+
+    print_section(&format!("Stable configuration ({})", name));
+    print!("{}", render_warehouse(&peeling.stable));
+    print_result("Rolls left", peeling.stable.rolls().len());
+}
+
+fn part01<W: Warehouse>(warehouse: &W, rule: &Rule) -> usize {
+    warehouse.clone().clean(rule)
+}
+
+fn part02<W: Warehouse>(warehouse: &W, rule: &Rule) -> usize {
+    warehouse.clone().clean_all(rule).iter().map(Vec::len).sum()
+}
+
+fn solve<W: Warehouse>(args: &Args, rule: &Rule) {
+    let example_data: W = parse(EXAMPLE);
+    let actual_data: W = parse(&read_input(4));
+
+    if args.flag("depths") {
+        print_peeling("Example", &example_data, rule);
+        print_peeling("Actual", &actual_data, rule);
+        return;
+    }
 
     print_section("Part 1 (Example)");
     print_result(
         "This is the number of rolls that can be accessed by the forklift",
-        part01(&example_data),
+        part01(&example_data, rule),
     );
 
     print_section("Part 1 (Actual)");
     print_result(
         "This is the number of rolls that can be accessed by the forklift",
-        part01(&actual_data),
+        part01(&actual_data, rule),
     );
 
     print_section("Part 2 (Example)");
     print_result(
         "This is the number of rolls that can be accessed by the forklift",
-        part02(&example_data),
+        part02(&example_data, rule),
     );
 
     print_section("Part 2 (Actual)");
     print_result(
-        "The dial passed through zero",
-        format!("{} times", part02(&actual_data)),
+        "This is the number of rolls that can be accessed by the forklift",
+        part02(&actual_data, rule),
     );
+
+    // `--waves` also shows how many rolls each pass of the forklift removed
+    if args.flag("waves") {
+        let waves: Vec<String> = actual_data
+            .clone()
+            .clean_all(rule)
+            .iter()
+            .map(|wave| wave.len().to_string())
+            .collect();
+        print_result("Rolls removed per wave", waves.join(", "));
+    }
+}
+
+pub fn day04(args: &Args) {
+    print_day_header(4);
+
+    // `--neighbourhood` and `--threshold` change which rolls the forklift can access
+    let rule = Rule {
+        neighbourhood: args.get("neighbourhood").unwrap_or_default(),
+        threshold: args.get("threshold").unwrap_or_default(),
+        // `--boundary torus` wraps the neighbourhoods around the edges
+        boundary: args.get("boundary").unwrap_or_default(),
+    };
+
+    // `--backend sparse` only stores the rolls, which is slower but gives the same answers
+    match args.get("backend").unwrap_or_default() {
+        Backend::Dense => solve::<Matrix>(args, &rule),
+        Backend::Sparse => solve::<Positions>(args, &rule),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day04_rules::Boundary;
+
+    #[test]
+    fn test_backends_agree() {
+        let dense: Matrix = parse(EXAMPLE);
+        let sparse: Positions = parse(EXAMPLE);
+        let rule = Rule::default();
+
+        assert_eq!((part01(&dense, &rule), part02(&dense, &rule)), (13, 43));
+        assert_eq!((part01(&sparse, &rule), part02(&sparse, &rule)), (13, 43));
+    }
+
+    /// The rules the tests run under, written `neighbourhood:threshold`.
+    const RULES: [&str; 6] = [
+        "moore:<4",
+        "von-neumann:<2",
+        "moore=2:<=10",
+        "hex:!=3",
+        "-1,0;0,1:<1",
+        "moore:>=5",
+    ];
+
+    fn rule(raw: &str, boundary: Boundary) -> Rule {
+        let (neighbourhood, threshold) = raw.split_once(':').unwrap();
+
+        Rule {
+            neighbourhood: neighbourhood.parse().unwrap(),
+            threshold: threshold.parse().unwrap(),
+            boundary,
+        }
+    }
+
+    #[test]
+    fn test_peel_depths() {
+        let example: Matrix = parse(EXAMPLE);
+        let rule = Rule::default();
+        let peeling = peel(&example, &rule);
+        let count = |expected: Depth| {
+            peeling
+                .depths
+                .iter()
+                .filter(|&(_, &d)| d == expected)
+                .count()
+        };
+
+        assert_eq!(count(Depth::Removed(1)), 13);
+        assert_eq!(count(Depth::Never), peeling.stable.rolls().len());
+        assert_eq!(count(Depth::Never) + 43, example.rolls().len());
+        assert_eq!(peeling.depths[(0, 2)], Depth::Removed(1));
+
+        // A roll can only go in wave n + 1 if one of its neighbours went in wave n
+        for ((i, j), &depth) in peeling.depths.iter() {
+            if let Depth::Removed(n) = depth
+                && n > 1
+            {
+                let neighbours = rule.neighbours((i as i32, j as i32), example.size());
+                assert!(
+                    neighbours
+                        .into_iter()
+                        .any(|(ni, nj)| peeling.depths[(ni as usize, nj as usize)]
+                            == Depth::Removed(n - 1))
+                );
+            }
+        }
+
+        let rendered = render_depths(&peeling.depths);
+        assert_eq!(rendered.lines().next(), Some("  ..11.1121."));
+        assert_eq!(
+            render_warehouse(&peeling.stable).matches('@').count(),
+            count(Depth::Never)
+        );
+        assert_eq!(
+            render_histogram(&peeling.waves).lines().count(),
+            peeling.waves.len()
+        );
+    }
+
+    #[test]
+    fn test_worklist_matches_waves() {
+        for raw in RULES {
+            for boundary in [Boundary::Bounded, Boundary::Torus] {
+                let rule = rule(raw, boundary);
+                let mut warehouse: Matrix = parse(EXAMPLE);
+                let removed_per_wave: Vec<usize> = warehouse
+                    .clone()
+                    .clean_all(&rule)
+                    .iter()
+                    .map(Vec::len)
+                    .collect();
+
+                let mut expected = Vec::new();
+                loop {
+                    match warehouse.clean(&rule) {
+                        0 => break,
+                        n => expected.push(n),
+                    }
+                }
+
+                assert_eq!(removed_per_wave, expected, "{} {:?}", raw, boundary);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backends_agree_on_boundaries() {
+        // Narrow enough for small tori to wrap offsets onto the same space
+        for raw_data in [EXAMPLE, "@@@\n@.@", "@@\n@@\n@@"] {
+            let dense: Matrix = parse(raw_data);
+            let sparse: Positions = parse(raw_data);
+
+            for raw in RULES {
+                for boundary in [Boundary::Bounded, Boundary::Torus, Boundary::Unbounded] {
+                    let rule = rule(raw, boundary);
+
+                    assert_eq!(
+                        dense.clone().clean_all(&rule),
+                        sparse.clone().clean_all(&rule),
+                        "{} {:?}",
+                        raw,
+                        boundary
+                    );
+                }
+
+                assert_eq!(
+                    part02(&dense, &rule(raw, Boundary::Bounded)),
+                    part02(&sparse, &rule(raw, Boundary::Unbounded))
+                );
+            }
+        }
+
+        // Wrapping gives the edge rolls more neighbours
+        let example: Matrix = parse(EXAMPLE);
+        assert!(part01(&example, &rule("moore:<4", Boundary::Torus)) < 13);
+    }
 }
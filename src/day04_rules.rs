@@ -0,0 +1,306 @@
+/// Which rolls the forklift can access: the neighbourhood counted around each roll and how many
+/// rolls it may have there.
+use std::{fmt::Display, str::FromStr};
+
+use crate::day04::Point;
+
+/// Rows read as a hexagonal grid, each one shifted half a space to the right of the one above
+const HEX_DELTAS: [Point; 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0)];
+
+const MAX_NEIGHBORS_TO_BE_CLEANABLE: usize = 4;
+
+/// `moore`, `von-neumann` (optionally with a radius, e.g. `moore=2`), `hex` or a custom list of
+/// `row,col` offsets separated by semicolons like `-1,0;1,0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbourhood {
+    deltas: Vec<Point>,
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Self {
+        // The 8 surrounding spaces
+        Neighbourhood::within(1, chebyshev)
+    }
+}
+
+fn chebyshev(di: i32, dj: i32) -> i32 {
+    di.abs().max(dj.abs())
+}
+
+fn manhattan(di: i32, dj: i32) -> i32 {
+    di.abs() + dj.abs()
+}
+
+impl Neighbourhood {
+    /// Every offset within `radius` for the given distance, except the space itself.
+    fn within(radius: i32, distance: fn(i32, i32) -> i32) -> Self {
+        let deltas = (-radius..=radius)
+            .flat_map(|di| (-radius..=radius).map(move |dj| (di, dj)))
+            .filter(|&(di, dj)| (di, dj) != (0, 0) && distance(di, dj) <= radius)
+            .collect();
+
+        Neighbourhood { deltas }
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, radius) = match s.split_once('=') {
+            Some((name, raw)) => match raw.parse::<i32>() {
+                Ok(radius) if radius > 0 => (name, radius),
+                _ => {
+                    return Err(format!(
+                        "Invalid radius: {} (expected a positive number)",
+                        raw
+                    ));
+                }
+            },
+            None => (s, 1),
+        };
+
+        match name {
+            "moore" => Ok(Neighbourhood::within(radius, chebyshev)),
+            "von-neumann" => Ok(Neighbourhood::within(radius, manhattan)),
+            "hex" if radius == 1 => Ok(Neighbourhood {
+                deltas: HEX_DELTAS.to_vec(),
+            }),
+            _ if s.contains(',') => {
+                let deltas = s
+                    .split(';')
+                    .map(|raw| {
+                        let (di, dj) = raw
+                            .split_once(',')
+                            .ok_or(format!("Invalid offset: {} (expected row,col)", raw))?;
+                        let parse = |raw: &str| {
+                            raw.trim()
+                                .parse::<i32>()
+                                .map_err(|e| format!("Invalid offset {}: {}", raw, e))
+                        };
+
+                        Ok((parse(di)?, parse(dj)?))
+                    })
+                    .collect::<Result<Vec<Point>, String>>()?;
+
+                if deltas.contains(&(0, 0)) {
+                    return Err("A roll can't be its own neighbour".to_string());
+                }
+
+                Ok(Neighbourhood { deltas })
+            }
+            _ => Err(format!(
+                "Invalid neighbourhood: {} (expected moore, von-neumann, hex or row,col;... offsets)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// How many neighbouring rolls still let the forklift in, e.g. `<4` or `>=2`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Threshold {
+    comparison: Comparison,
+    value: usize,
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Threshold {
+            comparison: Comparison::Less,
+            value: MAX_NEIGHBORS_TO_BE_CLEANABLE,
+        }
+    }
+}
+
+impl Threshold {
+    fn allows(&self, count: usize) -> bool {
+        match self.comparison {
+            Comparison::Less => count < self.value,
+            Comparison::LessOrEqual => count <= self.value,
+            Comparison::Greater => count > self.value,
+            Comparison::GreaterOrEqual => count >= self.value,
+            Comparison::Equal => count == self.value,
+            Comparison::NotEqual => count != self.value,
+        }
+    }
+}
+
+const COMPARISONS: [(&str, Comparison); 6] = [
+    // Two character operators first, so `<=` isn't read as `<`
+    ("<=", Comparison::LessOrEqual),
+    (">=", Comparison::GreaterOrEqual),
+    ("!=", Comparison::NotEqual),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+    ("=", Comparison::Equal),
+];
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (comparison, raw) = COMPARISONS
+            .iter()
+            .find_map(|&(op, comparison)| s.strip_prefix(op).map(|raw| (comparison, raw)))
+            .ok_or(format!(
+                "Invalid threshold: {} (expected <, <=, >, >=, = or != and a number)",
+                s
+            ))?;
+
+        Ok(Threshold {
+            comparison,
+            value: raw
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid threshold {}: {}", s, e))?,
+        })
+    }
+}
+
+impl Display for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (op, _) = COMPARISONS
+            .iter()
+            .find(|&&(_, comparison)| comparison == self.comparison)
+            .unwrap();
+
+        write!(f, "{}{}", op, self.value)
+    }
+}
+
+/// What lies past the edges of the warehouse.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Boundary {
+    /// Nothing: neighbours off the edge are empty
+    #[default]
+    Bounded,
+    /// The opposite edge, as if the warehouse was a torus
+    Torus,
+    /// More floor, with no limit on the coordinates. It is empty, so the answers match `Bounded`.
+    Unbounded,
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bounded" => Ok(Boundary::Bounded),
+            "torus" => Ok(Boundary::Torus),
+            "unbounded" => Ok(Boundary::Unbounded),
+            _ => Err(format!(
+                "Invalid boundary: {} (expected bounded, torus or unbounded)",
+                s
+            )),
+        }
+    }
+}
+
+impl Boundary {
+    /// Where `point` is on a warehouse of `size` (height and width), `None` if it's off the edge.
+    fn resolve(self, (i, j): Point, (height, width): Point) -> Option<Point> {
+        match self {
+            Boundary::Bounded => {
+                ((0..height).contains(&i) && (0..width).contains(&j)).then_some((i, j))
+            }
+            Boundary::Torus => Some((i.rem_euclid(height), j.rem_euclid(width))),
+            Boundary::Unbounded => Some((i, j)),
+        }
+    }
+}
+
+/// The forklift can access a roll when the rolls in its neighbourhood pass the threshold.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rule {
+    pub neighbourhood: Neighbourhood,
+    pub threshold: Threshold,
+    pub boundary: Boundary,
+}
+
+impl Rule {
+    pub fn is_accessible(&self, paper_neighbours: usize) -> bool {
+        self.threshold.allows(paper_neighbours)
+    }
+
+    /// The spaces counted around `point`. On a small torus several offsets can wrap onto the same
+    /// space, which is then counted several times.
+    pub fn neighbours(&self, (i, j): Point, size: Point) -> impl Iterator<Item = Point> {
+        self.neighbourhood
+            .deltas
+            .iter()
+            .filter_map(move |&(di, dj)| self.boundary.resolve((i + di, j + dj), size))
+    }
+
+    /// The spaces that count `point` among their neighbours, the reverse of `neighbours`.
+    pub fn neighbour_of(&self, (i, j): Point, size: Point) -> impl Iterator<Item = Point> {
+        self.neighbourhood
+            .deltas
+            .iter()
+            .filter_map(move |&(di, dj)| self.boundary.resolve((i - di, j - dj), size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let size = |raw: &str| raw.parse::<Neighbourhood>().unwrap().deltas.len();
+        assert_eq!(size("moore"), 8);
+        assert_eq!(size("moore=2"), 24);
+        assert_eq!(size("von-neumann"), 4);
+        assert_eq!(size("von-neumann=2"), 12);
+        assert_eq!(size("hex"), 6);
+        assert_eq!(size("-1,0;1,0;0,2"), 3);
+        assert_eq!(Neighbourhood::default(), "moore".parse().unwrap());
+
+        assert!("0,0;1,0".parse::<Neighbourhood>().is_err());
+        assert!("moore=0".parse::<Neighbourhood>().is_err());
+        assert!("hex=2".parse::<Neighbourhood>().is_err());
+
+        let threshold: Threshold = ">=3".parse().unwrap();
+        assert!(threshold.allows(3) && !threshold.allows(2));
+        assert_eq!(threshold.to_string(), ">=3");
+        assert_eq!(Threshold::default(), "<4".parse().unwrap());
+        assert!("4".parse::<Threshold>().is_err());
+
+        let rule = Rule {
+            boundary: Boundary::Torus,
+            ..Rule::default()
+        };
+        let mut wrapped: Vec<Point> = rule.neighbours((0, 0), (3, 3)).collect();
+        wrapped.sort_unstable();
+        assert_eq!(
+            wrapped,
+            vec![
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (2, 0),
+                (2, 1),
+                (2, 2)
+            ]
+        );
+        // A single row wraps onto itself
+        assert_eq!(
+            rule.neighbours((0, 0), (1, 2))
+                .filter(|&p| p == (0, 0))
+                .count(),
+            2
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use std::{mem, ops::Deref, str::FromStr};
 
-use crate::utils::{print_day_header, print_result, print_section, read_input};
+use crate::cli::Args;
+use crate::overflow::{Overflow, OverflowError};
+use crate::parsing::Block;
+use crate::utils::{print_checked_result, print_day_header, print_section, read_input};
 
 const EXAMPLE: &str = "
 123 328  51 64\x20
@@ -28,10 +31,12 @@ impl FromStr for Op {
 }
 
 impl Op {
-    fn apply(&self, numbers: &[u64]) -> u64 {
+    fn apply(&self, numbers: &[u64], overflow: Overflow) -> Result<u128, OverflowError> {
+        let numbers = numbers.iter().map(|&n| u128::from(n));
+
         match self {
-            Op::Add => numbers.iter().sum(),
-            Op::Mul => numbers.iter().product(),
+            Op::Add => overflow.sum(numbers),
+            Op::Mul => overflow.product(numbers),
         }
     }
 }
@@ -43,8 +48,8 @@ struct Problem {
 }
 
 impl Problem {
-    fn solve(&self) -> u64 {
-        self.op.apply(&self.numbers)
+    fn solve(&self, overflow: Overflow) -> Result<u128, OverflowError> {
+        self.op.apply(&self.numbers, overflow)
     }
 }
 
@@ -62,32 +67,31 @@ impl Deref for Problems {
 }
 
 impl Problems {
-    fn total(&self) -> u64 {
-        self.iter().map(|p| p.solve()).sum()
+    fn total(&self, overflow: Overflow) -> Result<u128, OverflowError> {
+        self.iter()
+            .try_fold(0, |acc, p| overflow.add(acc, p.solve(overflow)?))
     }
 }
 
-fn parse_operations(lines: &[&str]) -> Vec<Op> {
-    lines
-        .last()
-        .expect("No operations line found")
-        .split_whitespace()
-        .map(|raw_op| raw_op.parse().expect("Invalid operation"))
-        .collect()
+fn split_operations(raw_data: &str) -> (Block<'_>, Vec<Op>) {
+    let (number_rows, operations_row) = Block::new(raw_data)
+        .split_last_line()
+        .expect("No operations line found");
+
+    let operations = operations_row
+        .parse_fields()
+        .unwrap_or_else(|e| panic!("Invalid operation: {}", e))
+        .concat();
+
+    (number_rows, operations)
 }
 
 fn parse01(raw_data: &str) -> Problems {
-    let lines: Vec<_> = raw_data.trim().lines().collect();
-    let operations: Vec<_> = parse_operations(&lines);
-
-    let all_problems_numbers: Vec<Vec<u64>> = lines[..lines.len() - 1]
-        .iter()
-        .map(|&row| {
-            row.split_whitespace()
-                .map(|num_str| num_str.parse().expect("Invalid number"))
-                .collect()
-        })
-        .collect();
+    let (number_rows, operations) = split_operations(raw_data);
+
+    let all_problems_numbers: Vec<Vec<u64>> = number_rows
+        .parse_fields()
+        .unwrap_or_else(|e| panic!("Invalid number: {}", e));
 
     let problems = operations
         .into_iter()
@@ -101,31 +105,15 @@ fn parse01(raw_data: &str) -> Problems {
     Problems { problems }
 }
 
-fn parse_column(row_chars: &[Vec<char>], col_idx: usize) -> Option<u64> {
-    let vertical: String = row_chars
-        .iter()
-        .map(|chars| chars.get(col_idx).copied().unwrap_or(' '))
-        .collect();
-    vertical.trim().parse().ok()
-}
-
 fn parse02(raw_data: &str) -> Problems {
-    let lines: Vec<_> = raw_data.trim().lines().collect();
-    let operations = parse_operations(&lines);
-    let number_rows: Vec<_> = lines.iter().take(lines.len() - 1).copied().collect();
-
-    // Use the maximum line length to avoid missing columns
-    let max_width = number_rows.iter().map(|line| line.len()).max().unwrap_or(0);
-    let row_chars: Vec<Vec<char>> = number_rows
-        .iter()
-        .map(|line| line.chars().collect())
-        .collect();
+    let (number_rows, operations) = split_operations(raw_data);
 
     let mut problem_groups: Vec<Vec<u64>> = Vec::with_capacity(operations.len());
     let mut current_group: Vec<u64> = vec![];
 
-    for col_idx in 0..max_width {
-        match parse_column(&row_chars, col_idx) {
+    // Columns are padded to the longest line to avoid missing any
+    for column in number_rows.columns() {
+        match column.trim().parse().ok() {
             Some(num) => current_group.push(num),
             None if !current_group.is_empty() => {
                 // Hit the separator, flush the group
@@ -149,17 +137,19 @@ fn parse02(raw_data: &str) -> Problems {
     Problems { problems }
 }
 
-fn part01(problems: &Problems) -> u64 {
-    problems.total()
+fn part01(problems: &Problems, overflow: Overflow) -> Result<u128, OverflowError> {
+    problems.total(overflow)
 }
 
-fn part02(problems: &Problems) -> u64 {
-    problems.total()
+fn part02(problems: &Problems, overflow: Overflow) -> Result<u128, OverflowError> {
+    problems.total(overflow)
 }
 
-pub fn day06() {
+pub fn day06(args: &Args) {
     print_day_header(6);
 
+    let overflow = args.get("overflow").unwrap_or_default();
+
     let example_data_01 = parse01(EXAMPLE);
     let actual_data_01 = parse01(&read_input(6));
 
@@ -169,14 +159,14 @@ pub fn day06() {
     // dbg!(actual_data_02);
 
     print_section("Part 1 (Example)");
-    print_result("The total number is: ", part01(&example_data_01));
+    print_checked_result("The total number is: ", part01(&example_data_01, overflow));
 
     print_section("Part 1 (Actual)");
-    print_result("The total number is: ", part01(&actual_data_01));
+    print_checked_result("The total number is: ", part01(&actual_data_01, overflow));
 
     print_section("Part 2 (Example)");
-    print_result("The total number is: ", part02(&example_data_02));
+    print_checked_result("The total number is: ", part02(&example_data_02, overflow));
 
     print_section("Part 2 (Actual)");
-    print_result("The total number is: ", part02(&actual_data_02));
+    print_checked_result("The total number is: ", part02(&actual_data_02, overflow));
 }
@@ -1,4 +1,11 @@
-use crate::utils::{print_day_header, print_result, print_section, read_input};
+use crate::cli::Args;
+use crate::graph;
+use crate::grid::{Grid, Pos};
+use crate::overflow::{Overflow, OverflowError};
+use crate::parsing::Block;
+use crate::utils::{
+    print_checked_result, print_day_header, print_result, print_section, read_input,
+};
 use std::{
     collections::{HashMap, HashSet},
     str::FromStr,
@@ -23,8 +30,6 @@ const EXAMPLE: &str = "
 ...............
 ";
 
-type Pos = (usize, usize);
-
 #[derive(Debug)]
 enum Space {
     Empty,
@@ -45,7 +50,7 @@ impl FromStr for Space {
 
 #[derive(Debug)]
 struct Matrix {
-    matrix: Vec<Vec<Space>>,
+    matrix: Grid<Space>,
     start: Pos,
 }
 
@@ -53,39 +58,49 @@ impl FromStr for Matrix {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.trim();
-        let start_col = s
+        let block = Block::new(s);
+        let start_col = block
+            .text()
             .chars()
             .position(|ch| ch == 'S')
             .ok_or("Couldn't find start position")?;
 
-        let matrix: Result<Vec<Vec<Space>>, _> = s
-            .lines()
-            .map(|line| line.chars().map(|ch| ch.to_string().parse()).collect())
-            .collect();
+        let matrix = block
+            .parse_grid(|ch| ch.to_string().parse::<Space>())
+            .map_err(|e| e.to_string())?;
 
         Ok(Matrix {
-            matrix: matrix?,
+            matrix,
             start: (0, start_col),
         })
     }
 }
 
 impl Matrix {
-    fn rows(&self) -> &[Vec<Space>] {
-        &self.matrix
-    }
-
-    fn height(&self) -> usize {
-        self.matrix.len()
+    fn rows(&self) -> impl Iterator<Item = &[Space]> {
+        self.matrix.rows()
     }
 
     fn width(&self) -> usize {
-        self.matrix[0].len()
+        self.matrix.width()
     }
 
     fn get(&self, row: usize, col: usize) -> Option<&Space> {
-        self.matrix.get(row).and_then(|r| r.get(col))
+        self.matrix.get(row, col)
+    }
+
+    /// Where the beam at `(i, j)` goes next: straight down, or to both sides of a splitter below it.
+    fn beam_moves(&self, &(i, j): &Pos) -> Vec<Pos> {
+        match self.get(i + 1, j) {
+            None => vec![],
+            Some(Space::Empty) => vec![(i + 1, j)],
+            Some(Space::Splitter) => [j.checked_sub(1), Some(j + 1)]
+                .into_iter()
+                .flatten()
+                .filter(|&jj| jj < self.width())
+                .map(|jj| (i + 1, jj))
+                .collect(),
+        }
     }
 }
 
@@ -94,61 +109,53 @@ fn parse(raw_data: &str) -> Matrix {
 }
 
 fn part01(matrix: &Matrix) -> usize {
-    let mut exploration_queue: Vec<Pos> = vec![matrix.start];
-    let mut n_splits_hit: HashSet<Pos> = HashSet::new();
-
-    while let Some((i, j)) = exploration_queue.pop() {
-        let (ii, jj) = (i + 1, j);
+    let beams = graph::from_fn(|pos: &Pos| matrix.beam_moves(pos));
 
-        if ii >= matrix.height() || jj >= matrix.width() || n_splits_hit.contains(&(ii, jj)) {
-            continue;
-        }
-
-        let space = matrix.get(ii, jj).expect("Should find it");
-
-        match &space {
-            Space::Empty => exploration_queue.push((ii, jj)),
-            Space::Splitter => {
-                exploration_queue.extend_from_slice(&[(ii, jj - 1), (ii, jj + 1)]);
-                n_splits_hit.insert((ii, jj));
-            }
-        }
-    }
+    let n_splits_hit: HashSet<Pos> = graph::dfs(&beams, matrix.start)
+        .into_iter()
+        .map(|(i, j)| (i + 1, j))
+        .filter(|&(i, j)| matches!(matrix.get(i, j), Some(Space::Splitter)))
+        .collect();
 
     n_splits_hit.len()
 }
 
-fn part02(matrix: &Matrix) -> u64 {
-    let mut timelines: HashMap<usize, u64> = HashMap::from([(matrix.start.1, 1)]);
+fn part02(matrix: &Matrix, overflow: Overflow) -> Result<u128, OverflowError> {
+    let mut timelines: HashMap<usize, u128> = HashMap::from([(matrix.start.1, 1)]);
 
-    for row in matrix.rows().iter().skip(1) {
-        let mut next_timelines: HashMap<usize, u64> = HashMap::new();
+    for row in matrix.rows().skip(1) {
+        let mut next_timelines: HashMap<usize, u128> = HashMap::new();
+        let mut add = |col: usize, count: u128| -> Result<(), OverflowError> {
+            let entry = next_timelines.entry(col).or_default();
+            *entry = overflow.add(*entry, count)?;
+            Ok(())
+        };
 
         for (&col, &count) in &timelines {
             match &row[col] {
                 Space::Splitter => {
                     if let Some(left) = col.checked_sub(1) {
-                        *next_timelines.entry(left).or_default() += count;
+                        add(left, count)?;
                     }
                     if col + 1 < matrix.width() {
-                        *next_timelines.entry(col + 1).or_default() += count;
+                        add(col + 1, count)?;
                     }
                 }
-                Space::Empty => {
-                    *next_timelines.entry(col).or_default() += count;
-                }
+                Space::Empty => add(col, count)?,
             }
         }
 
         timelines = next_timelines;
     }
 
-    timelines.values().sum()
+    overflow.sum(timelines.into_values())
 }
 
-pub fn day07() {
+pub fn day07(args: &Args) {
     print_day_header(7);
 
+    let overflow = args.get("overflow").unwrap_or_default();
+
     let example_data = parse(EXAMPLE);
     let actual_data = parse(&read_input(7));
 
@@ -159,10 +166,16 @@ pub fn day07() {
     print_result("This is the number of splits", part01(&actual_data));
 
     print_section("Part 2 (Example)");
-    print_result("This is the number of splits", part02(&example_data));
+    print_checked_result(
+        "This is the number of splits",
+        part02(&example_data, overflow),
+    );
 
     print_section("Part 2 (Actual)");
-    print_result("This is the number of splits", part02(&actual_data));
+    print_checked_result(
+        "This is the number of splits",
+        part02(&actual_data, overflow),
+    );
 }
 
 #[cfg(test)]
@@ -178,6 +191,6 @@ mod tests {
     #[test]
     fn test_part02_example() {
         let matrix = parse(EXAMPLE);
-        assert_eq!(part02(&matrix), 40)
+        assert_eq!(part02(&matrix, Overflow::Unchecked), Ok(40))
     }
 }
@@ -1,5 +1,7 @@
+use crate::parsing::Block;
+use crate::union_find::UnionFind;
 use crate::utils::{print_day_header, print_result, print_section, read_input};
-use std::{collections::HashSet, str::FromStr};
+use std::str::FromStr;
 
 const EXAMPLE: &str = "
 162,817,812
@@ -71,14 +73,13 @@ impl Pos {
 type JunctionBoxes = Vec<Pos>;
 
 fn parse(raw_data: &str) -> JunctionBoxes {
-    raw_data
-        .trim()
-        .lines()
-        .map(|line| line.parse().expect("Line not valid"))
-        .collect()
+    Block::new(raw_data)
+        .parse_lines()
+        .unwrap_or_else(|e| panic!("Line not valid: {}", e))
 }
 
-fn part01(junction_boxes: &JunctionBoxes, n_connections: u16) -> usize {
+/// Every pair of junction boxes, closest first.
+fn sorted_pairs(junction_boxes: &JunctionBoxes) -> Vec<(usize, usize, i64)> {
     let mut distances: Vec<(usize, usize, i64)> = Vec::new();
 
     for (i, x) in junction_boxes.iter().enumerate() {
@@ -88,72 +89,29 @@ fn part01(junction_boxes: &JunctionBoxes, n_connections: u16) -> usize {
     }
 
     distances.sort_unstable_by_key(|(_, _, d)| *d);
-    let mut connections: Vec<HashSet<usize>> = Vec::new();
-
-    for (i, j, _) in distances.drain(..n_connections as usize) {
-        let i_circ = connections.iter().position(|c| c.contains(&i));
-        let j_circ = connections.iter().position(|c| c.contains(&j));
-
-        match (i_circ, j_circ) {
-            (None, None) => {
-                connections.push(HashSet::from([i, j]));
-            }
-            (None, Some(jc)) => {
-                connections[jc].insert(i);
-            }
-            (Some(ic), None) => {
-                connections[ic].insert(j);
-            }
-            (Some(ic), Some(jc)) if ic == jc => {}
-            (Some(ic), Some(jc)) => {
-                let j_set = connections.remove(jc);
-                let i_idx = if ic > jc { ic - 1 } else { ic };
-                connections[i_idx].extend(j_set);
-            }
-        }
+    distances
+}
+
+fn part01(junction_boxes: &JunctionBoxes, n_connections: u16) -> usize {
+    let mut circuits = UnionFind::new(junction_boxes.len());
+
+    for (i, j, _) in sorted_pairs(junction_boxes)
+        .into_iter()
+        .take(n_connections as usize)
+    {
+        circuits.union(i, j);
     }
 
-    let mut circuit_sizes: Vec<usize> = connections.iter().map(|c| c.len()).collect();
+    let mut circuit_sizes = circuits.component_sizes();
     circuit_sizes.sort_unstable();
     circuit_sizes.into_iter().rev().take(3).product()
 }
 
 fn part02(junction_boxes: &JunctionBoxes) -> Option<i64> {
-    let mut distances: Vec<(usize, usize, i64)> = Vec::new();
-
-    for (i, x) in junction_boxes.iter().enumerate() {
-        for (j, y) in junction_boxes.iter().enumerate().skip(i + 1) {
-            distances.push((i, j, x.dist_squared(y)));
-        }
-    }
-
-    distances.sort_unstable_by_key(|(_, _, d)| *d);
-
-    let mut connections: Vec<HashSet<usize>> = Vec::new();
-
-    for (i, j, _) in distances.drain(..) {
-        let i_circ = connections.iter().position(|c| c.contains(&i));
-        let j_circ = connections.iter().position(|c| c.contains(&j));
-
-        match (i_circ, j_circ) {
-            (None, None) => {
-                connections.push(HashSet::from([i, j]));
-            }
-            (None, Some(jc)) => {
-                connections[jc].insert(i);
-            }
-            (Some(ic), None) => {
-                connections[ic].insert(j);
-            }
-            (Some(ic), Some(jc)) if ic == jc => {}
-            (Some(ic), Some(jc)) => {
-                let j_set = connections.remove(jc);
-                let i_idx = if ic > jc { ic - 1 } else { ic };
-                connections[i_idx].extend(j_set);
-            }
-        }
+    let mut circuits = UnionFind::new(junction_boxes.len());
 
-        if connections.len() == 1 && connections[0].len() == junction_boxes.len() {
+    for (i, j, _) in sorted_pairs(junction_boxes) {
+        if circuits.union(i, j) && circuits.n_components() == 1 {
             return Some(junction_boxes[i].x * junction_boxes[j].x);
         }
     }
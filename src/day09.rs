@@ -1,3 +1,4 @@
+use crate::parsing::Block;
 use crate::utils::{print_day_header, print_result, print_section, read_input};
 use std::{collections::HashSet, str::FromStr};
 
@@ -44,11 +45,9 @@ impl Pos {
 type RedTiles = Vec<Pos>;
 
 fn parse(raw_data: &str) -> RedTiles {
-    raw_data
-        .trim()
-        .lines()
-        .map(|line| line.parse().expect("Line not valid"))
-        .collect()
+    Block::new(raw_data)
+        .parse_lines()
+        .unwrap_or_else(|e| panic!("Line not valid: {}", e))
 }
 
 fn part01(red_tiles: &RedTiles) -> usize {
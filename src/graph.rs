@@ -0,0 +1,492 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+    marker::PhantomData,
+};
+
+use crate::grid::{Grid, Pos};
+
+/// Graph whose edges are computed on demand from each node.
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    fn neighbours(&self, node: &Self::Node) -> impl Iterator<Item = Self::Node>;
+}
+
+/// Graph with a cost on every edge. Every `Graph` is one, with all edges costing 1.
+pub trait WeightedGraph {
+    type Node: Clone + Eq + Hash;
+
+    fn edges(&self, node: &Self::Node) -> impl Iterator<Item = (Self::Node, u64)>;
+}
+
+impl<G: Graph> WeightedGraph for G {
+    type Node = G::Node;
+
+    fn edges(&self, node: &Self::Node) -> impl Iterator<Item = (Self::Node, u64)> {
+        self.neighbours(node).map(|n| (n, 1))
+    }
+}
+
+/// Every orthogonally adjacent cell inside the grid. Use `from_fn` to add walls or other rules.
+impl<T> Graph for Grid<T> {
+    type Node = Pos;
+
+    fn neighbours(&self, &(row, col): &Pos) -> impl Iterator<Item = Pos> {
+        [
+            row.checked_sub(1).map(|r| (r, col)),
+            Some((row + 1, col)),
+            col.checked_sub(1).map(|c| (row, c)),
+            Some((row, col + 1)),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|&(r, c)| self.get(r, c).is_some())
+    }
+}
+
+pub struct FnGraph<N, F> {
+    neighbours: F,
+    node: PhantomData<N>,
+}
+
+impl<N, F, I> Graph for FnGraph<N, F>
+where
+    N: Clone + Eq + Hash,
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    type Node = N;
+
+    fn neighbours(&self, node: &N) -> impl Iterator<Item = N> {
+        (self.neighbours)(node).into_iter()
+    }
+}
+
+/// Turns a closure returning the neighbours of a node into a graph.
+pub fn from_fn<N, F>(neighbours: F) -> FnGraph<N, F> {
+    FnGraph {
+        neighbours,
+        node: PhantomData,
+    }
+}
+
+pub struct WeightedFnGraph<N, F> {
+    edges: F,
+    node: PhantomData<N>,
+}
+
+impl<N, F, I> WeightedGraph for WeightedFnGraph<N, F>
+where
+    N: Clone + Eq + Hash,
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    type Node = N;
+
+    fn edges(&self, node: &N) -> impl Iterator<Item = (N, u64)> {
+        (self.edges)(node).into_iter()
+    }
+}
+
+/// Turns a closure returning `(neighbour, cost)` pairs into a weighted graph.
+pub fn weighted_from_fn<N, F>(edges: F) -> WeightedFnGraph<N, F> {
+    WeightedFnGraph {
+        edges,
+        node: PhantomData,
+    }
+}
+
+/// Result of exploring a graph from a single start node.
+#[derive(Debug, Clone)]
+pub struct Search<N> {
+    start: N,
+    distances: HashMap<N, u64>,
+    parents: HashMap<N, N>,
+}
+
+impl<N: Clone + Eq + Hash> Search<N> {
+    fn new(start: N) -> Self {
+        Search {
+            distances: HashMap::from([(start.clone(), 0)]),
+            parents: HashMap::new(),
+            start,
+        }
+    }
+
+    #[allow(dead_code)] // Not every puzzle needs it
+    pub fn distance(&self, node: &N) -> Option<u64> {
+        self.distances.get(node).copied()
+    }
+
+    #[allow(dead_code)] // Not every puzzle needs it
+    pub fn distances(&self) -> &HashMap<N, u64> {
+        &self.distances
+    }
+
+    /// Nodes from the start to `target`, both included.
+    pub fn path_to(&self, target: &N) -> Option<Vec<N>> {
+        self.distances.get(target)?;
+
+        let mut path = vec![target.clone()];
+        while let Some(parent) = self.parents.get(path.last().expect("Never empty")) {
+            path.push(parent.clone());
+        }
+
+        path.reverse();
+        debug_assert!(path[0] == self.start);
+        Some(path)
+    }
+}
+
+#[allow(dead_code)] // Not every puzzle needs it
+pub fn bfs<G: Graph>(graph: &G, start: G::Node) -> Search<G::Node> {
+    let mut search = Search::new(start.clone());
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        let distance = search.distances[&node];
+
+        for next in graph.neighbours(&node) {
+            if !search.distances.contains_key(&next) {
+                search.distances.insert(next.clone(), distance + 1);
+                search.parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+
+    search
+}
+
+/// Every node reachable from `start`, in depth-first preorder.
+pub fn dfs<G: Graph>(graph: &G, start: G::Node) -> Vec<G::Node> {
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+
+    while let Some(node) = stack.pop() {
+        if !visited.insert(node.clone()) {
+            continue;
+        }
+
+        // Pushed in reverse so the first neighbour is explored first
+        let neighbours: Vec<_> = graph.neighbours(&node).collect();
+        stack.extend(
+            neighbours
+                .into_iter()
+                .rev()
+                .filter(|n| !visited.contains(n)),
+        );
+        order.push(node);
+    }
+
+    order
+}
+
+#[allow(dead_code)] // Not every puzzle needs it
+pub fn dijkstra<G: WeightedGraph>(graph: &G, start: G::Node) -> Search<G::Node> {
+    let mut search = Search::new(start.clone());
+    let mut settled = HashSet::new();
+    let mut frontier = Frontier::new();
+    frontier.push(start, 0);
+
+    while let Some((node, distance)) = frontier.pop() {
+        if !settled.insert(node.clone()) {
+            continue;
+        }
+
+        for (next, cost) in graph.edges(&node) {
+            let next_distance = distance + cost;
+
+            if search
+                .distances
+                .get(&next)
+                .is_none_or(|&d| next_distance < d)
+            {
+                search.distances.insert(next.clone(), next_distance);
+                search.parents.insert(next.clone(), node.clone());
+                frontier.push(next, next_distance);
+            }
+        }
+    }
+
+    search
+}
+
+/// Cheapest path from `start` to a node satisfying `is_goal`. `heuristic` must never overestimate
+/// the remaining cost, otherwise the path found might not be the cheapest. Nodes are expanded
+/// again when a cheaper way to them turns up, so it doesn't need to be consistent.
+pub fn astar<G: WeightedGraph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> u64,
+) -> Option<(u64, Vec<G::Node>)> {
+    let mut search = Search::new(start.clone());
+    let mut frontier = Frontier::new();
+    frontier.push(start.clone(), heuristic(&start));
+
+    while let Some((node, priority)) = frontier.pop() {
+        let distance = search.distances[&node];
+
+        // A cheaper way to the node was found after this entry was pushed
+        if priority > distance + heuristic(&node) {
+            continue;
+        }
+
+        if is_goal(&node) {
+            let path = search.path_to(&node).expect("Goal was reached");
+            return Some((distance, path));
+        }
+
+        for (next, cost) in graph.edges(&node) {
+            let next_distance = distance + cost;
+
+            if search
+                .distances
+                .get(&next)
+                .is_none_or(|&d| next_distance < d)
+            {
+                search.distances.insert(next.clone(), next_distance);
+                search.parents.insert(next.clone(), node.clone());
+                frontier.push(next.clone(), next_distance + heuristic(&next));
+            }
+        }
+    }
+
+    None
+}
+
+/// Orders `nodes` so that every edge goes from an earlier node to a later one. Nodes reachable
+/// from `nodes` are included too. Fails with a node that is part of a cycle.
+#[allow(dead_code)] // Not every puzzle needs it
+pub fn topological_sort<G: Graph>(
+    graph: &G,
+    nodes: impl IntoIterator<Item = G::Node>,
+) -> Result<Vec<G::Node>, G::Node> {
+    enum Mark {
+        InProgress,
+        Done,
+    }
+
+    let mut marks: HashMap<G::Node, Mark> = HashMap::new();
+    let mut order = Vec::new();
+
+    for root in nodes {
+        if marks.contains_key(&root) {
+            continue;
+        }
+
+        // (node, whether its neighbours were already pushed)
+        let mut stack = vec![(root, false)];
+
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                marks.insert(node.clone(), Mark::Done);
+                order.push(node);
+                continue;
+            }
+
+            match marks.get(&node) {
+                Some(Mark::Done) => continue,
+                Some(Mark::InProgress) => return Err(node),
+                None => {}
+            }
+
+            marks.insert(node.clone(), Mark::InProgress);
+            stack.push((node.clone(), true));
+
+            for next in graph.neighbours(&node) {
+                match marks.get(&next) {
+                    Some(Mark::InProgress) => return Err(next),
+                    Some(Mark::Done) => {}
+                    None => stack.push((next, false)),
+                }
+            }
+        }
+    }
+
+    order.reverse();
+    Ok(order)
+}
+
+/// Groups `nodes` (and everything reachable from them) into connected components. Edges are
+/// assumed to go both ways.
+#[allow(dead_code)] // Not every puzzle needs it
+pub fn connected_components<G: Graph>(
+    graph: &G,
+    nodes: impl IntoIterator<Item = G::Node>,
+) -> Vec<Vec<G::Node>> {
+    let mut seen = HashSet::new();
+    let mut components = Vec::new();
+
+    for node in nodes {
+        if seen.contains(&node) {
+            continue;
+        }
+
+        let component = dfs(graph, node);
+        seen.extend(component.iter().cloned());
+        components.push(component);
+    }
+
+    components
+}
+
+/// Min-priority queue that doesn't need the nodes to be `Ord`. Equal priorities pop in the order
+/// they were pushed.
+struct Frontier<N> {
+    heap: BinaryHeap<Reverse<Entry<N>>>,
+    pushed: usize,
+}
+
+struct Entry<N> {
+    priority: u64,
+    seq: usize,
+    node: N,
+}
+
+impl<N> PartialEq for Entry<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N> Eq for Entry<N> {}
+
+impl<N> PartialOrd for Entry<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for Entry<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.seq).cmp(&(other.priority, other.seq))
+    }
+}
+
+impl<N> Frontier<N> {
+    fn new() -> Self {
+        Frontier {
+            heap: BinaryHeap::new(),
+            pushed: 0,
+        }
+    }
+
+    fn push(&mut self, node: N, priority: u64) {
+        self.heap.push(Reverse(Entry {
+            priority,
+            seq: self.pushed,
+            node,
+        }));
+        self.pushed += 1;
+    }
+
+    fn pop(&mut self) -> Option<(N, u64)> {
+        let Reverse(entry) = self.heap.pop()?;
+        Some((entry.node, entry.priority))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::Block;
+
+    const MAZE: &str = "
+..#.
+.##.
+....";
+
+    fn maze() -> Grid<bool> {
+        Block::new(MAZE)
+            .parse_grid(|ch| Ok::<_, String>(ch == '#'))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bfs_on_grid() {
+        let grid = maze();
+        let open = from_fn(|p: &Pos| grid.neighbours(p).filter(|&q| !grid[q]).collect::<Vec<_>>());
+        let search = bfs(&open, (0, 0));
+
+        assert_eq!(search.distance(&(0, 3)), Some(7));
+        assert_eq!(search.distance(&(0, 2)), None);
+        assert_eq!(search.path_to(&(2, 0)), Some(vec![(0, 0), (1, 0), (2, 0)]));
+
+        // Without walls every cell is reachable
+        assert_eq!(bfs(&grid, (0, 0)).distance(&(0, 3)), Some(3));
+    }
+
+    #[test]
+    fn test_dijkstra_and_astar_agree() {
+        let graph = weighted_from_fn(|&n: &u32| match n {
+            0 => vec![(1, 7), (2, 2)],
+            1 => vec![(3, 1)],
+            2 => vec![(1, 3), (3, 8)],
+            _ => vec![],
+        });
+
+        let search = dijkstra(&graph, 0);
+        assert_eq!(search.distance(&3), Some(6));
+        assert_eq!(search.path_to(&3), Some(vec![0, 2, 1, 3]));
+
+        let found = astar(&graph, 0, |&n| n == 3, |_| 0);
+        assert_eq!(found, Some((6, vec![0, 2, 1, 3])));
+    }
+
+    #[test]
+    fn test_astar_with_inconsistent_heuristic() {
+        let graph = weighted_from_fn(|&n: &u32| match n {
+            0 => vec![(1, 1), (2, 1)],
+            1 => vec![(3, 1)],
+            2 => vec![(3, 3)],
+            3 => vec![(4, 3)],
+            _ => vec![],
+        });
+        // Admissible, but 1 looks worse than it is so 3 is first reached through 2
+        let heuristic = |&n: &u32| if n == 1 { 4 } else { 0 };
+
+        let found = astar(&graph, 0, |&n| n == 4, heuristic);
+        assert_eq!(found, Some((5, vec![0, 1, 3, 4])));
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let dag = from_fn(|&n: &u32| match n {
+            0 => vec![1, 2],
+            1 => vec![3],
+            2 => vec![3],
+            _ => vec![],
+        });
+        let order = topological_sort(&dag, [0]).unwrap();
+        let idx = |n| order.iter().position(|&m| m == n).unwrap();
+
+        assert_eq!(order.len(), 4);
+        assert!(idx(0) < idx(1) && idx(1) < idx(3) && idx(2) < idx(3));
+
+        let cycle = from_fn(|&n: &u32| [(n + 1) % 3]);
+        assert!(topological_sort(&cycle, [0]).is_err());
+    }
+
+    #[test]
+    fn test_dfs_and_components() {
+        let graph = from_fn(|&n: &u32| match n {
+            0 => vec![1],
+            1 => vec![0, 2],
+            2 => vec![1],
+            3 => vec![4],
+            4 => vec![3],
+            _ => vec![],
+        });
+
+        assert_eq!(dfs(&graph, 0), vec![0, 1, 2]);
+        assert_eq!(
+            connected_components(&graph, 0..6),
+            vec![vec![0, 1, 2], vec![3, 4], vec![5]]
+        );
+    }
+}
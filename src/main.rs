@@ -1,14 +1,30 @@
+mod big_decimal;
+mod cli;
 mod day01;
+mod day01_lock;
+mod day01_plan;
 mod day02;
+mod day02_catalogue;
+mod day02_predicate;
 mod day03;
+mod day03_strategy;
 mod day04;
+mod day04_alt;
+mod day04_rules;
 mod day05;
 mod day06;
 mod day07;
 mod day08;
 mod day09;
+mod graph;
+mod grid;
+mod overflow;
+mod parsing;
+mod range_set;
+mod union_find;
 mod utils;
 
+use cli::Args;
 use day01::day01;
 use day02::day02;
 use day03::day03;
@@ -18,27 +34,25 @@ use day06::day06;
 use day07::day07;
 use day08::day08;
 use day09::day09;
-use std::env;
 
 const DEFAULT_DAY: usize = 9;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args = Args::from_env();
 
-    let day = if args.len() > 1 {
-        args[1].parse().unwrap_or(DEFAULT_DAY)
-    } else {
-        DEFAULT_DAY
-    };
+    let day = args
+        .day()
+        .and_then(|day| day.parse().ok())
+        .unwrap_or(DEFAULT_DAY);
 
     match day {
-        1 => day01(),
-        2 => day02(),
-        3 => day03(),
-        4 => day04(),
+        1 => day01(&args),
+        2 => day02(&args),
+        3 => day03(&args),
+        4 => day04(&args),
         5 => day05(),
-        6 => day06(),
-        7 => day07(),
+        6 => day06(&args),
+        7 => day07(&args),
         8 => day08(),
         9 => day09(),
         _ => println!("Day {} not implemented yet!", day),
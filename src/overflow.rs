@@ -0,0 +1,115 @@
+use std::{fmt::Display, str::FromStr};
+
+/// What to do when an answer doesn't fit in a `u64`. Values are carried around as `u128` so the
+/// same code works for every mode.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Overflow {
+    /// Plain `u64` arithmetic: panics in debug builds and silently wraps in release builds
+    #[default]
+    Unchecked,
+    /// Fail as soon as a value doesn't fit in a `u64`
+    Error,
+    /// Carry on in `u128`, only failing if that overflows too
+    Widen,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unchecked" => Ok(Overflow::Unchecked),
+            "error" => Ok(Overflow::Error),
+            "widen" => Ok(Overflow::Widen),
+            _ => Err(format!(
+                "Invalid overflow mode: {} (expected unchecked, error or widen)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverflowError {
+    op: char,
+    lhs: u128,
+    rhs: u128,
+    bits: u32,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} overflows u{}",
+            self.lhs, self.op, self.rhs, self.bits
+        )
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+impl Overflow {
+    pub fn add(self, lhs: u128, rhs: u128) -> Result<u128, OverflowError> {
+        match self {
+            Overflow::Unchecked => Ok((lhs as u64 + rhs as u64) as u128),
+            Overflow::Error => (lhs as u64)
+                .checked_add(rhs as u64)
+                .filter(|_| lhs <= u64::MAX as u128 && rhs <= u64::MAX as u128)
+                .map(u128::from)
+                .ok_or(self.error('+', lhs, rhs)),
+            Overflow::Widen => lhs.checked_add(rhs).ok_or(self.error('+', lhs, rhs)),
+        }
+    }
+
+    pub fn mul(self, lhs: u128, rhs: u128) -> Result<u128, OverflowError> {
+        match self {
+            Overflow::Unchecked => Ok((lhs as u64 * rhs as u64) as u128),
+            Overflow::Error => (lhs as u64)
+                .checked_mul(rhs as u64)
+                .filter(|_| lhs <= u64::MAX as u128 && rhs <= u64::MAX as u128)
+                .map(u128::from)
+                .ok_or(self.error('*', lhs, rhs)),
+            Overflow::Widen => lhs.checked_mul(rhs).ok_or(self.error('*', lhs, rhs)),
+        }
+    }
+
+    pub fn sum(self, values: impl IntoIterator<Item = u128>) -> Result<u128, OverflowError> {
+        values
+            .into_iter()
+            .try_fold(0, |acc, value| self.add(acc, value))
+    }
+
+    pub fn product(self, values: impl IntoIterator<Item = u128>) -> Result<u128, OverflowError> {
+        values
+            .into_iter()
+            .try_fold(1, |acc, value| self.mul(acc, value))
+    }
+
+    fn error(self, op: char, lhs: u128, rhs: u128) -> OverflowError {
+        let bits = match self {
+            Overflow::Widen => u128::BITS,
+            _ => u64::BITS,
+        };
+
+        OverflowError { op, lhs, rhs, bits }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes() {
+        let big = u64::MAX as u128;
+
+        assert_eq!(Overflow::Error.add(big - 1, 1), Ok(big));
+        assert!(Overflow::Error.add(big, 1).is_err());
+        assert_eq!(Overflow::Widen.add(big, 1), Ok(big + 1));
+
+        assert!(Overflow::Error.product([1 << 32, 1 << 32]).is_err());
+        assert_eq!(Overflow::Widen.product([1 << 32, 1 << 32]), Ok(1 << 64));
+        assert!(Overflow::Widen.product([1 << 64, 1 << 64]).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::grid::Grid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based line in the original input
+    pub line: usize,
+    /// 1-based column (in chars), when the error can be pinned to one
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "line {}, column {}: {}", self.line, column, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A piece of the input that remembers on which line it starts, so that errors raised while
+/// parsing it still point at the right place in the original file.
+#[derive(Debug, Clone, Copy)]
+pub struct Block<'a> {
+    text: &'a str,
+    first_line: usize,
+}
+
+impl<'a> Block<'a> {
+    /// Wraps the whole input, dropping blank lines around it. Unlike `str::trim`, the indentation
+    /// of the first line is kept, since fixed-width inputs depend on it.
+    pub fn new(raw: &'a str) -> Self {
+        let mut text = raw;
+        let mut first_line = 1;
+
+        while let Some((line, rest)) = text.split_once('\n') {
+            if !line.trim().is_empty() {
+                break;
+            }
+            text = rest;
+            first_line += 1;
+        }
+
+        Block {
+            text: text.trim_end(),
+            first_line,
+        }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// 1-based line of the original input the block starts on.
+    pub fn line(&self) -> usize {
+        self.first_line
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Block<'a>> + use<'a> {
+        let first_line = self.first_line;
+
+        self.text.lines().enumerate().map(move |(i, text)| Block {
+            text,
+            first_line: first_line + i,
+        })
+    }
+
+    /// Splits on blank lines, e.g. the two halves of day 5.
+    pub fn sections(&self) -> Vec<Block<'a>> {
+        let mut sections = Vec::new();
+        let mut start: Option<(usize, usize)> = None;
+        let mut offset = 0;
+
+        for (i, line) in self.text.split('\n').enumerate() {
+            if line.trim().is_empty() {
+                if let Some((start_offset, first_line)) = start.take() {
+                    sections.push(Block {
+                        // Leave the newline of the last line out
+                        text: &self.text[start_offset..offset - 1],
+                        first_line,
+                    });
+                }
+            } else if start.is_none() {
+                start = Some((offset, self.first_line + i));
+            }
+
+            offset += line.len() + 1;
+        }
+
+        if let Some((start_offset, first_line)) = start {
+            sections.push(Block {
+                text: &self.text[start_offset..],
+                first_line,
+            });
+        }
+
+        sections
+    }
+
+    /// Separates the last line from everything above it, e.g. the operators row of day 6.
+    pub fn split_last_line(&self) -> Option<(Block<'a>, Block<'a>)> {
+        let last = self.lines().last()?;
+        let head_len = self.offset_of(last.text).saturating_sub(1);
+
+        Some((
+            Block {
+                text: &self.text[..head_len],
+                first_line: self.first_line,
+            },
+            last,
+        ))
+    }
+
+    pub fn error(&self, column: Option<usize>, message: impl Display) -> ParseError {
+        ParseError {
+            line: self.first_line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    /// Error pointing at `inner`, which must be a subslice of this block.
+    pub fn error_at(&self, inner: &str, message: impl Display) -> ParseError {
+        let before = &self.text[..self.offset_of(inner)];
+        let line = self.first_line + before.matches('\n').count();
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+        ParseError {
+            line,
+            column: Some(column),
+            message: message.to_string(),
+        }
+    }
+
+    /// One value per line.
+    pub fn parse_lines<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.parse_lines_with(|line| line.parse::<T>())
+    }
+
+    pub fn parse_lines_with<T, E: Display>(
+        &self,
+        mut parse: impl FnMut(&'a str) -> Result<T, E>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.lines()
+            .map(|line| {
+                parse(line.text)
+                    .map_err(|e| line.error(None, format!("Invalid value {:?}: {}", line.text, e)))
+            })
+            .collect()
+    }
+
+    /// Values separated by `separator`, possibly spread across several lines (e.g. day 2).
+    /// Surrounding whitespace is ignored, as are empty items such as a trailing separator.
+    pub fn parse_list<T>(&self, separator: char) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.text
+            .split(separator)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.parse()
+                    .map_err(|e| self.error_at(item, format!("Invalid value {:?}: {}", item, e)))
+            })
+            .collect()
+    }
+
+    /// Whitespace separated values on every line, e.g. the rows of numbers in day 6.
+    pub fn parse_fields<T>(&self) -> Result<Vec<Vec<T>>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.text
+            .lines()
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|field| {
+                        field.parse().map_err(|e| {
+                            self.error_at(field, format!("Invalid value {:?}: {}", field, e))
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// One value per character, rows may have different lengths.
+    pub fn parse_chars<T, E: Display>(
+        &self,
+        mut parse: impl FnMut(char) -> Result<T, E>,
+    ) -> Result<Vec<Vec<T>>, ParseError> {
+        self.lines()
+            .map(|line| {
+                line.text
+                    .chars()
+                    .enumerate()
+                    .map(|(j, ch)| {
+                        parse(ch).map_err(|e| {
+                            line.error(Some(j + 1), format!("Invalid character {:?}: {}", ch, e))
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Like `parse_chars`, but every row must have the same length.
+    pub fn parse_grid<T, E: Display>(
+        &self,
+        parse: impl FnMut(char) -> Result<T, E>,
+    ) -> Result<Grid<T>, ParseError> {
+        let rows = self.parse_chars(parse)?;
+        let width = rows.first().map_or(0, |row| row.len());
+
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(ParseError {
+                line: self.first_line + i,
+                column: None,
+                message: format!("Row has {} cells, expected {}", rows[i].len(), width),
+            });
+        }
+
+        Ok(Grid::from_rows(rows).expect("Rows were already checked"))
+    }
+
+    /// The text read top to bottom for every character column, padding short lines with spaces.
+    pub fn columns(&self) -> Vec<String> {
+        let rows: Vec<Vec<char>> = self
+            .text
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        (0..width)
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row.get(col).copied().unwrap_or(' '))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn offset_of(&self, inner: &str) -> usize {
+        let offset = (inner.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        assert!(offset <= self.text.len(), "Not a slice of this block");
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_keep_line_numbers() {
+        let block = Block::new("\n3-5\n10-14\n\n1\nx\n");
+        let sections = block.sections();
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].text(), "3-5\n10-14");
+
+        let error = sections[1].parse_lines::<u64>().unwrap_err();
+        assert_eq!(error.line, 6);
+    }
+
+    #[test]
+    fn test_parse_list_reports_column() {
+        let block = Block::new("1,2,\n3,x4");
+        let error = block.parse_list::<u32>(',').unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, Some(3)));
+        assert_eq!(
+            Block::new("1,2,\n3,").parse_list::<u32>(','),
+            Ok(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_grid_and_columns() {
+        let block = Block::new(" 12\n3 4");
+        assert_eq!(block.columns(), vec![" 3", "1 ", "24"]);
+
+        let error = Block::new("ab\nabc")
+            .parse_grid(Ok::<_, String>)
+            .unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let grid = Block::new("ab\ncd").parse_grid(Ok::<_, String>).unwrap();
+        assert_eq!(grid[(1, 0)], 'c');
+    }
+}
@@ -0,0 +1,312 @@
+use std::{fmt::Display, num::ParseIntError, str::FromStr};
+
+/// Integers that can be used as bounds of an inclusive range.
+pub trait Discrete: Copy + Ord {
+    const MIN: Self;
+    const MAX: Self;
+
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    /// Number of values in `start..=end`
+    fn span(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn span(start: Self, end: Self) -> u128 {
+                    (end - start) as u128 + 1
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(u8, u16, u32, u64, usize);
+
+/// Inclusive range, `start` and `end` are both part of it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Range<T = u64> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Discrete> Range<T> {
+    pub fn new(start: T, end: T) -> Self {
+        Range { start, end }
+    }
+
+    pub fn contains(&self, n: T) -> bool {
+        (self.start..=self.end).contains(&n)
+    }
+
+    pub fn len(&self) -> u128 {
+        if self.is_empty() {
+            0
+        } else {
+            T::span(self.start, self.end)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseRangeError {
+    MissingDash,
+    InvalidStart(ParseIntError),
+    InvalidEnd(ParseIntError),
+}
+
+impl Display for ParseRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRangeError::MissingDash => write!(f, "Range must contain a dash"),
+            ParseRangeError::InvalidStart(e) => write!(f, "Invalid start value: {}", e),
+            ParseRangeError::InvalidEnd(e) => write!(f, "Invalid end value: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseRangeError {}
+
+impl<T: FromStr<Err = ParseIntError>> FromStr for Range<T> {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (raw_start, raw_end) = s.split_once('-').ok_or(ParseRangeError::MissingDash)?;
+
+        Ok(Range {
+            start: raw_start
+                .trim()
+                .parse()
+                .map_err(ParseRangeError::InvalidStart)?,
+            end: raw_end
+                .trim()
+                .parse()
+                .map_err(ParseRangeError::InvalidEnd)?,
+        })
+    }
+}
+
+/// Set of integers stored as sorted, disjoint and non-adjacent inclusive ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RangeSet<T = u64> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Discrete> RangeSet<T> {
+    pub fn new() -> Self {
+        RangeSet { ranges: Vec::new() }
+    }
+
+    /// Adds `range`, merging it with every range it overlaps or touches. Empty ranges are ignored.
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // First range that isn't strictly to the left of `range` (with a gap between them)
+        let lo = self
+            .ranges
+            .partition_point(|r| r.end.succ().is_some_and(|next| next < range.start));
+        // One past the last range that isn't strictly to the right of `range`
+        let hi = self
+            .ranges
+            .partition_point(|r| range.end.succ().is_none_or(|next| r.start <= next));
+
+        let merged = if lo < hi {
+            Range {
+                start: range.start.min(self.ranges[lo].start),
+                end: range.end.max(self.ranges[hi - 1].end),
+            }
+        } else {
+            range
+        };
+
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    pub fn contains(&self, n: T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end < n);
+        self.ranges.get(idx).is_some_and(|r| r.start <= n)
+    }
+
+    /// Total number of values covered by the set.
+    pub fn len(&self) -> u128 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.ranges.iter()
+    }
+
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut result = self.clone();
+        for &range in &other.ranges {
+            result.insert(range);
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+
+            if start <= end {
+                ranges.push(Range { start, end });
+            }
+
+            // Whichever ends first can't overlap anything else in the other set
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        RangeSet { ranges }
+    }
+
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        self.intersection(&other.complement(Range::new(T::MIN, T::MAX)))
+    }
+
+    /// Values inside `bounds` that are not in the set.
+    pub fn complement(&self, bounds: Range<T>) -> RangeSet<T> {
+        let mut ranges = Vec::new();
+        let mut next_start = Some(bounds.start);
+
+        for range in &self.ranges {
+            let Some(start) = next_start else { break };
+
+            if range.start > bounds.end {
+                break;
+            }
+
+            if range.start > start {
+                ranges.push(Range {
+                    start,
+                    end: range.start.pred().expect("Greater than another value"),
+                });
+            }
+
+            next_start = next_start
+                .max(range.end.succ())
+                .filter(|_| range.end < T::MAX);
+        }
+
+        if let Some(start) = next_start.filter(|&start| start <= bounds.end) {
+            ranges.push(Range {
+                start,
+                end: bounds.end,
+            });
+        }
+
+        RangeSet { ranges }
+    }
+}
+
+impl<T: Discrete> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges: Vec<Range<T>> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort();
+
+        let mut merged: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end.succ().is_none_or(|next| range.start <= next) => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        RangeSet { ranges: merged }
+    }
+}
+
+impl<'a, T: Discrete> IntoIterator for &'a RangeSet<T> {
+    type Item = &'a Range<T>;
+    type IntoIter = std::slice::Iter<'a, Range<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(u64, u64)]) -> RangeSet<u64> {
+        ranges.iter().map(|&(s, e)| Range::new(s, e)).collect()
+    }
+
+    fn pairs(set: &RangeSet<u64>) -> Vec<(u64, u64)> {
+        set.iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn test_insert_merges_overlapping_and_adjacent() {
+        let mut ranges = RangeSet::new();
+        for (s, e) in [(10, 14), (3, 5), (16, 20), (12, 18), (6, 6), (30, 29)] {
+            ranges.insert(Range::new(s, e));
+        }
+
+        assert_eq!(pairs(&ranges), vec![(3, 6), (10, 20)]);
+        assert_eq!(ranges, set(&[(10, 14), (3, 5), (16, 20), (12, 18), (6, 6)]));
+        assert_eq!(ranges.len(), 15);
+        assert!(ranges.contains(6) && ranges.contains(20) && !ranges.contains(7));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(1, 10), (20, 30)]);
+        let b = set(&[(5, 25), (40, 41)]);
+
+        assert_eq!(pairs(&a.union(&b)), vec![(1, 30), (40, 41)]);
+        assert_eq!(pairs(&a.intersection(&b)), vec![(5, 10), (20, 25)]);
+        assert_eq!(pairs(&a.difference(&b)), vec![(1, 4), (26, 30)]);
+        assert_eq!(
+            pairs(&a.complement(Range::new(0, 35))),
+            vec![(0, 0), (11, 19), (31, 35)]
+        );
+    }
+
+    #[test]
+    fn test_complement_at_the_edges() {
+        let full = set(&[(0, u64::MAX)]);
+        assert!(full.complement(Range::new(0, u64::MAX)).is_empty());
+        assert_eq!(full.len(), 1 << 64);
+
+        let edges = set(&[(0, 1), (u64::MAX - 1, u64::MAX)]);
+        assert_eq!(
+            pairs(&edges.complement(Range::new(0, u64::MAX))),
+            vec![(2, u64::MAX - 2)]
+        );
+    }
+}
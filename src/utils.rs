@@ -1,11 +1,26 @@
 use std::fs;
 
+use crate::cli::Args;
+
+pub fn input_path(day: u32) -> String {
+    format!("data/day{:02}.txt", day)
+}
+
 pub fn read_input(day: u32) -> String {
-    let filename = format!("data/day{:02}.txt", day);
+    let filename = input_path(day);
     fs::read_to_string(&filename)
         .unwrap_or_else(|_| panic!("Failed to read input file: {}", filename))
 }
 
+/// The file given with `--input`, or the day's puzzle input otherwise.
+pub fn read_input_from(args: &Args, day: u32) -> String {
+    match args.value("input") {
+        Some(path) => fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Failed to read input file: {}", path)),
+        None => read_input(day),
+    }
+}
+
 pub fn print_day_header(day: u32) {
     println!("\n╔════════════════════════════════╗");
     println!("║        Day \x1b[1m{:02}\x1b[0m Solutions        ║", day);
@@ -19,3 +34,14 @@ pub fn print_section(title: &str) {
 pub fn print_result(label: &str, value: impl std::fmt::Display) {
     println!("  {}: \x1b[1m{}\x1b[0m", label, value);
 }
+
+/// Like `print_result`, but shows the error instead when the answer couldn't be computed.
+pub fn print_checked_result(
+    label: &str,
+    value: Result<impl std::fmt::Display, impl std::fmt::Display>,
+) {
+    match value {
+        Ok(value) => print_result(label, value),
+        Err(e) => println!("  {}: \x1b[31m{}\x1b[0m", label, e),
+    }
+}